@@ -0,0 +1,119 @@
+//! Clipboard access.
+
+use crate::{connection::Connection, error::Error};
+
+#[cfg(target_os = "linux")]
+use crate::util::sync;
+
+/// Which system selection a [`Clipboard`] accesses.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Selection {
+    /// The clipboard which is used by explicit copy and paste commands.
+    Clipboard,
+
+    /// The selection which holds whatever was most recently selected, and is usually pasted by clicking the middle
+    /// mouse button. This only exists on X11: on other platforms it's always empty, and setting it does nothing.
+    Primary,
+}
+
+/// A handle for reading and writing a system selection, created with [`Connection::clipboard`] or
+/// [`Connection::primary_selection`].
+///
+/// On X11, the data you set stays owned by your application, and other applications ask for it when they want to
+/// paste. This means that reading or writing a selection may wait for other applications to respond, and that it's
+/// lost when the [`Connection`] is dropped, unless a clipboard manager takes a copy (see
+/// [`Connection::persist_clipboard`]).
+#[derive(Clone)]
+pub struct Clipboard {
+    connection: Connection,
+    selection: Selection,
+}
+
+impl Clipboard {
+    pub(crate) fn new(connection: Connection, selection: Selection) -> Self {
+        Self { connection, selection }
+    }
+
+    /// Returns which selection this accesses.
+    pub fn selection(&self) -> Selection {
+        self.selection
+    }
+
+    /// Returns the formats the selection's contents are available in. These are usually MIME types such as
+    /// `text/plain;charset=utf-8`, `text/html` or `image/png`, but may also include platform-specific names, such as
+    /// `UTF8_STRING` on X11.
+    ///
+    /// Currently this is only implemented on Linux, and other platforms will return an empty list.
+    pub fn targets(&self) -> Vec<String> {
+        #[cfg(target_os = "linux")]
+        {
+            sync::mutex_lock(&*self.connection.0).clipboard_targets(self.selection)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Vec::new()
+        }
+    }
+
+    /// Returns the selection's contents in the given format, as listed by [`targets`](Self::targets), or `None` if
+    /// they aren't available in that format.
+    ///
+    /// Currently this is only implemented on Linux, and other platforms will return `None`.
+    pub fn get(&self, mime_type: &str) -> Option<Vec<u8>> {
+        #[cfg(target_os = "linux")]
+        {
+            sync::mutex_lock(&*self.connection.0).clipboard_get(self.selection, mime_type)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = mime_type;
+            None
+        }
+    }
+
+    /// Replaces the contents of the selection with the same data in one or more formats, given as pairs of MIME
+    /// type and data. Offering several formats, such as `text/html` along with `text/plain;charset=utf-8`, lets
+    /// the application which pastes it pick whichever suits it best.
+    ///
+    /// Currently this is only implemented on Linux, and other platforms will return [`Error::Unsupported`].
+    pub fn set(&self, formats: &[(&str, &[u8])]) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        {
+            sync::mutex_lock(&*self.connection.0).clipboard_set(self.selection, formats)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = formats;
+            Err(Error::Unsupported)
+        }
+    }
+
+    /// Returns the text in the selection, or `None` if it's empty or its contents can't be converted to text.
+    ///
+    /// Currently this is only implemented on Linux, and other platforms will return `None`.
+    pub fn get_text(&self) -> Option<String> {
+        #[cfg(target_os = "linux")]
+        {
+            sync::mutex_lock(&*self.connection.0).clipboard_get_text(self.selection)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            None
+        }
+    }
+
+    /// Replaces the contents of the selection with the given text.
+    ///
+    /// Currently this is only implemented on Linux, and other platforms will return [`Error::Unsupported`].
+    pub fn set_text(&self, text: &str) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        {
+            sync::mutex_lock(&*self.connection.0).clipboard_set_text(self.selection, text)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = text;
+            Err(Error::Unsupported)
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use crate::{error::Error, platform::imp, window::Builder};
+use crate::{clipboard::{Clipboard, Selection}, error::Error, platform::imp, window::Builder};
+#[cfg(feature = "input")]
+use crate::input::{Key, KeyboardLayouts, Modifiers};
 use crate::util::sync::Mutex;
 use std::sync::Arc;
 
@@ -38,6 +40,116 @@ impl Connection {
         Builder::new(self, None)
     }
 
+    /// Returns a handle for reading and writing the system clipboard.
+    pub fn clipboard(&self) -> Clipboard {
+        Clipboard::new(self.clone(), Selection::Clipboard)
+    }
+
+    /// Returns a handle for reading and writing the primary selection, which is what gets pasted by clicking the
+    /// middle mouse button on X11. See [`Selection::Primary`].
+    pub fn primary_selection(&self) -> Clipboard {
+        Clipboard::new(self.clone(), Selection::Primary)
+    }
+
+    /// Asks the system's clipboard manager to take a copy of what this application has put on the
+    /// [`clipboard`](Self::clipboard), so that it's still available to paste after the application exits, and waits
+    /// for it to finish.
+    ///
+    /// This is done automatically when the connection is closed, so it only needs calling if the application may
+    /// exit without dropping everything, such as with [`std::process::exit`]. Returns [`Error::Unsupported`] if no
+    /// clipboard manager is running.
+    ///
+    /// Currently this is only implemented on Linux, where it's needed because copied data is normally served by the
+    /// application it was copied from. Other platforms will return `Ok(())`.
+    pub fn persist_clipboard(&self) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        {
+            sync::mutex_lock(&*self.0).persist_clipboard()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Ok(())
+        }
+    }
+
+    /// Returns the user's keyboard layouts, and which of them is active. A
+    /// [`KeyboardLayoutChanged`](crate::event::Event::KeyboardLayoutChanged) event is sent to every window when this
+    /// changes.
+    /// 
+    /// Currently this is only implemented on Linux, and other platforms will return an empty list.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    pub fn keyboard_layouts(&self) -> KeyboardLayouts {
+        #[cfg(target_os = "linux")]
+        {
+            sync::mutex_lock(&*self.0).keyboard_layouts()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            KeyboardLayouts::default()
+        }
+    }
+
+    /// Switches to the keyboard layout at the given index into
+    /// [`KeyboardLayouts::names`](KeyboardLayouts::names). Out-of-range indices are ignored.
+    /// 
+    /// This affects the whole desktop, not just this application's windows.
+    /// Currently this is only implemented on Linux.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    pub fn set_keyboard_layout(&self, idx: usize) {
+        #[cfg(target_os = "linux")]
+        {
+            sync::mutex_lock(&*self.0).set_keyboard_layout(idx)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = idx;
+        }
+    }
+
+    /// Returns a label for the given [`Key`] according to what it types in the active keyboard layout, suitable for
+    /// showing to the user. For example, the key which is [`Key::BracketLeft`] on a US keyboard is labelled "Ü" on a
+    /// German one. Keys which don't type anything are labelled with their name.
+    /// 
+    /// As in that example, a key the layout doesn't have is labelled by whatever is in its place on a US keyboard.
+    /// Pressing that key reports what the layout actually has there instead (here, [`Key::Unknown`] for "Ü"), so
+    /// shortcuts which should follow the key's position rather than its meaning are better matched by
+    /// [`ScanCode`](crate::input::ScanCode).
+    /// 
+    /// Currently this is only implemented on Linux, and other platforms will always return the key's name.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    pub fn key_label(&self, key: Key) -> String {
+        #[cfg(target_os = "linux")]
+        {
+            sync::mutex_lock(&*self.0).key_label(key)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            key.to_string()
+        }
+    }
+
+    /// Finds which [`Key`] types the given [`char`] in the active keyboard layout, along with the [`Modifiers`]
+    /// which need to be held while pressing it. Returns `None` if the layout can't type it, or if it needs a modifier
+    /// which [`Modifiers`] can't represent, such as AltGr.
+    /// 
+    /// Currently this is only implemented on Linux, and other platforms will always return `None`.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    pub fn key_for_char(&self, ch: char) -> Option<(Key, Modifiers)> {
+        #[cfg(target_os = "linux")]
+        {
+            sync::mutex_lock(&*self.0).key_for_char(ch)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = ch;
+            None
+        }
+    }
+
     #[cfg(unix)]
     pub fn xscreenid(&self) -> u32 {
         let g = sync::mutex_lock(&*self.0);
@@ -0,0 +1,39 @@
+//! Drag and drop.
+
+use std::path::PathBuf;
+
+/// Something that was dropped onto a window, reported by [`Event::Drop`](crate::event::Event::Drop).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DropData {
+    /// One or more files, such as from a file manager.
+    Paths(Vec<PathBuf>),
+
+    /// Data in the format given by `mime_type`, such as `text/plain;charset=utf-8`. This is used when the drag
+    /// doesn't contain any local files.
+    Data { mime_type: String, data: Vec<u8> },
+}
+
+/// Something to drag out of a window with [`Window::start_drag`](crate::window::Window::start_drag).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DragData {
+    /// One or more files, which can be dropped into a file manager or any other application which opens files.
+    /// Relative paths are taken to be relative to the current directory.
+    Paths(Vec<PathBuf>),
+
+    /// The same data in one or more formats, given as pairs of MIME type and data. Offering several formats lets the
+    /// application it's dropped into pick whichever suits it best.
+    Data(Vec<(String, Vec<u8>)>),
+}
+
+/// What the application something is dropped into does with it.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum DragAction {
+    /// The data is copied, leaving the original alone.
+    Copy,
+
+    /// The data is moved, so the original should be deleted once the drag has ended.
+    Move,
+
+    /// A link to the original is made, such as a shortcut to a file.
+    Link,
+}
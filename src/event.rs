@@ -1,7 +1,8 @@
+use crate::{clipboard::Selection, dnd::{DragAction, DropData}};
 #[cfg(feature = "input")]
-use crate::input::{Key, MouseButton};
+use crate::input::{Key, Modifiers, MouseButton, PenTool, ScanCode, ScrollUnit, TouchPhase};
 
-#[derive(Copy, Clone, Debug, Hash, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 #[non_exhaustive]
 pub enum Event {
     /// A request has been made to close the window, most likely by clicking the 'x' button or by pressing alt+F4.
@@ -29,41 +30,113 @@ pub enum Event {
     /// The window's visibility has changed.
     Visible(bool),
 
+    /// The contents of a clipboard [`Selection`] were replaced, either by this application or by another one. This
+    /// is sent to every window.
+    /// 
+    /// Currently this is only reported on Linux, where it needs the XFixes extension, which all modern X servers have.
+    ClipboardChanged { selection: Selection },
+
+    /// Something being dragged from another application has entered the window. This is only sent to windows which
+    /// have opted in with [`Builder::accept_drops`](crate::window::Builder::accept_drops).
+    /// 
+    /// - `position` is relative to the top-left of the window's inner drawable area, in pixels.
+    /// - `mime_types` lists the formats the dragged data is available in. Files are offered as `text/uri-list`.
+    /// 
+    /// Currently this is only reported on Linux.
+    DragEnter { position: (i16, i16), mime_types: Vec<String> },
+
+    /// Something being dragged from another application has moved over the window, following an
+    /// [`Event::DragEnter`].
+    DragMove { position: (i16, i16) },
+
+    /// Something being dragged from another application has left the window without being dropped, or the drop failed.
+    DragLeave,
+
+    /// Something dragged from another application was dropped onto the window.
+    /// 
+    /// If it contains local files, they're reported as [`DropData::Paths`]. Otherwise the data is fetched in the
+    /// first format the source offered, which is usually the one it prefers.
+    Drop { position: (i16, i16), data: DropData },
+
+    /// A drag started with [`Window::start_drag`](crate::window::Window::start_drag) has ended. `action` is what the
+    /// application it was dropped into did with it, or `None` if it was cancelled, refused or dropped somewhere which
+    /// doesn't accept drops. If it's [`DragAction::Move`], the original should now be deleted.
+    DragEnd { action: Option<DragAction> },
+
     /// A [`Key`] was pressed.
     /// 
     /// This event will tell you which physical key the user has pressed. If your aim is to process text input,
     /// consider using the [`Event::Input`] event type instead.
+    /// 
+    /// - `key` is the [`Key`] according to the user's keyboard layout.
+    /// - `scancode` is the layout-independent [`ScanCode`] of the physical key.
+    /// - `keycode` is the raw, platform-specific code for the key: an X11 keycode on Linux, or a scan code on Windows
+    ///   (with `0xE000` added for extended keys).
+    /// - `modifiers` are the [`Modifiers`] as they were just before the key was pressed, so if the key is itself a
+    ///   modifier, it isn't included yet - an [`Event::ModifiersChanged`] will follow instead.
     #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
     #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
-    KeyboardDown(Key),
+    KeyboardDown { key: Key, scancode: ScanCode, keycode: u32, modifiers: Modifiers },
 
     /// A [`Key`] was auto-repeated by the system because the user is holding it down.
     /// 
     /// This event will tell you which physical key the user is holding. If your aim is to process text input,
-    /// consider using the [`Event::Input`] event type instead.
+    /// consider using the [`Event::Input`] event type instead. The fields are the same as [`Event::KeyboardDown`].
     #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
     #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
-    KeyboardRepeat(Key),
+    KeyboardRepeat { key: Key, scancode: ScanCode, keycode: u32, modifiers: Modifiers },
 
-    /// A [`Key`] was released.
+    /// A [`Key`] was released. The fields are the same as [`Event::KeyboardDown`].
+    /// 
+    /// As with [`Event::KeyboardDown`], `modifiers` are from just before the key was released, so releasing a modifier
+    /// key still includes it, with an [`Event::ModifiersChanged`] following to remove it.
     #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
     #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
-    KeyboardUp(Key),
+    KeyboardUp { key: Key, scancode: ScanCode, keycode: u32, modifiers: Modifiers },
+
+    /// The set of held or locked [`Modifiers`] has changed.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    ModifiersChanged(Modifiers),
+
+    /// The active keyboard layout was switched, or the set of available layouts changed. The new layouts can be
+    /// looked up with [`Connection::keyboard_layouts`](crate::connection::Connection::keyboard_layouts).
+    /// 
+    /// Currently this is only reported on Linux.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    KeyboardLayoutChanged,
 
     /// A [`char`] was typed using the keyboard.
     #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
     #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
     Input(char),
 
+    /// The text being composed in an input method editor (IME) has changed. This is only sent to windows which have
+    /// opted in with [`Window::set_ime_allowed`](crate::window::Window::set_ime_allowed).
+    /// 
+    /// The text should be drawn inline at the caret, usually underlined, until the composition is committed or
+    /// cancelled. `cursor_range` is the byte range of the IME's cursor within `text`, or `None` if it should be
+    /// hidden. An empty `text` means the composition has ended.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    ImePreedit { text: String, cursor_range: Option<(usize, usize)> },
+
+    /// An input method editor (IME) has finished composing some text, which should be inserted at the caret.
+    /// This text is not also sent as [`Event::Input`].
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    ImeCommit(String),
+
     /// A [`MouseButton`] was pressed.
     #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
     #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
-    MouseDown(MouseButton),
+    MouseDown { button: MouseButton, modifiers: Modifiers },
 
     /// A [`MouseButton`] was released.
     #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
     #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
-    MouseUp(MouseButton),
+    MouseUp { button: MouseButton, modifiers: Modifiers },
 
     /// The mouse was moved to a new position on the screen. The position is reported in pixels relative to the
     /// top-left of the user's desktop.
@@ -71,6 +144,24 @@ pub enum Event {
     #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
     MouseMove((u16, u16)),
 
+    /// The mouse was physically moved by the given amount, before any pointer acceleration is applied.
+    /// 
+    /// Unlike [`Event::MouseMove`], this is not limited by the edges of the window or screen, and may be reported in
+    /// fractions of a pixel, so it's well suited for first-person camera controls. Only the focused window receives
+    /// this event. It's usually paired with [`CursorMode::Locked`](crate::window::CursorMode::Locked).
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    RawMouseMotion { dx: f64, dy: f64 },
+
+    /// The mouse wheel or touchpad was scrolled. Positive `dx` scrolls to the right, and positive `dy` scrolls up, away
+    /// from the user.
+    /// 
+    /// This is reported alongside [`Event::ScrollUp`] and [`Event::ScrollDown`], and unlike them it also covers
+    /// horizontal and high-resolution scrolling.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    Scroll { dx: f64, dy: f64, unit: ScrollUnit },
+
     /// The mouse wheel was scrolled up, away from the user.
     #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
     #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
@@ -90,4 +181,75 @@ pub enum Event {
     #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
     #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
     MouseLeave,
+
+    /// A finger touched, moved on, or was lifted from a touch screen.
+    /// 
+    /// - `id` identifies the touch from when it [`Started`](TouchPhase::Started) until it
+    ///   [`Ended`](TouchPhase::Ended) or was [`Cancelled`](TouchPhase::Cancelled), and may be reused afterwards.
+    /// - `position` is relative to the top-left of the window's inner drawable area, in pixels.
+    /// 
+    /// Touches on a window which receives this event are not also reported as mouse events.
+    /// Currently this is only reported on Linux.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    Touch { id: u64, phase: TouchPhase, position: (f64, f64) },
+
+    /// The user is pinching or rotating with multiple fingers on a touchpad, usually to zoom or rotate content.
+    /// 
+    /// - `delta_scale` is how much the distance between the fingers has changed since the last event, relative to
+    ///   the distance when the gesture started. Positive values mean the fingers are moving apart (zooming in).
+    /// - `delta_angle` is how far the fingers have rotated since the last event, in degrees clockwise.
+    /// 
+    /// Currently this is only reported on Linux, with XInput 2.4 or later.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    PinchGesture { delta_scale: f64, delta_angle: f64, phase: TouchPhase },
+
+    /// The user is swiping with multiple fingers on a touchpad.
+    /// 
+    /// - `dx` and `dy` are how far the fingers have moved since the last event, in pixels, with positive values
+    ///   meaning right and down.
+    /// - `fingers` is the number of fingers on the touchpad.
+    /// 
+    /// Currently this is only reported on Linux, with XInput 2.4 or later. There's no event for holding fingers still
+    /// on the touchpad, as XInput has no hold gesture (libinput's is only exposed to Wayland clients).
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    SwipeGesture { dx: f64, dy: f64, fingers: u32, phase: TouchPhase },
+
+    /// A stylus moved over, touched or was lifted from a drawing tablet.
+    /// 
+    /// - `position` is relative to the top-left of the window's inner drawable area, in pixels.
+    /// - `pressure` ranges from `0.0` to `1.0`.
+    /// - `tilt` is how far the pen is leaning along the X and Y axes, from `-1.0` to `1.0`, with `0.0` meaning upright.
+    /// - `buttons` has a bit set for each button held on the barrel of the pen: bit 0 for the lower button and bit 1
+    ///   for the upper one.
+    /// - `phase` is [`Started`](TouchPhase::Started) when the pen touches the tablet and [`Ended`](TouchPhase::Ended)
+    ///   when it's lifted. It's [`Moved`](TouchPhase::Moved) otherwise, including while hovering.
+    /// 
+    /// Pens also move the mouse cursor, so they're reported as mouse events as well.
+    /// Currently this is only reported on Linux, where any device that senses pressure is taken to be a pen.
+    /// These events only arrive while the pen is moving the cursor, so a pen which is lifted out of range just stops
+    /// sending them, see [`Event::PenProximity`].
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    Pen {
+        position: (f64, f64),
+        pressure: f64,
+        tilt: (f64, f64),
+        buttons: u8,
+        tool: PenTool,
+        phase: TouchPhase,
+    },
+
+    /// A stylus came close enough to a drawing tablet to be sensed (`entered` is `true`), or moved away from it.
+    /// 
+    /// X11 doesn't report this directly, so on Linux a pen is considered to have entered when its first event
+    /// arrives, and to have left when the cursor leaves the window or is moved by another device. That means taking
+    /// the pen out of range isn't reported until one of those things happens, and a pen which leaves and comes back
+    /// to the window is reported as entering again even if it never left the tablet's range.
+    /// Currently this is only reported on Linux.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    PenProximity { tool: PenTool, entered: bool },
 }
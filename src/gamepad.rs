@@ -0,0 +1,450 @@
+//! Gamepad and joystick input.
+//!
+//! Gamepads are handled separately from windows, since they aren't tied to any window's focus. Create a
+//! [`Gamepads`] object, call [`poll_events`](Gamepads::poll_events) once per frame, and read the results from
+//! [`events`](Gamepads::events) or from the state getters.
+//!
+//! Raw device input is translated into the standard layout of [`Button`]s and [`Axis`]es using mappings in the format
+//! of the community-maintained [SDL_GameControllerDB](https://github.com/gabomdq/SDL_GameControllerDB), which can be
+//! loaded with [`add_mappings`](Gamepads::add_mappings). Devices without a mapping fall back to the Linux kernel's
+//! standard gamepad layout, which most modern controllers follow.
+
+#[cfg(target_os = "linux")]
+mod evdev;
+mod mapping;
+
+use self::mapping::{Mapping, Range, Source, Target};
+use crate::error::Error;
+use std::time::Duration;
+
+const EV_KEY: u16 = 0x01;
+const EV_ABS: u16 = 0x03;
+
+/// A standard gamepad button, named after its position on an Xbox-style controller.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Button {
+    /// The bottom face button
+    A,
+    /// The right face button
+    B,
+    /// The left face button
+    X,
+    /// The top face button
+    Y,
+    Back,
+    Guide,
+    Start,
+    LeftStick,
+    RightStick,
+    LeftShoulder,
+    RightShoulder,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    /// An extra button, such as Share or Capture
+    Misc1,
+    Paddle1,
+    Paddle2,
+    Paddle3,
+    Paddle4,
+    Touchpad,
+}
+
+const BUTTON_COUNT: usize = Button::Touchpad as usize + 1;
+
+/// A standard gamepad axis.
+///
+/// Stick axes range from -1.0 to 1.0, with positive Y being down. Trigger axes range from 0.0 to 1.0.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Axis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+const AXIS_COUNT: usize = Axis::RightTrigger as usize + 1;
+
+/// Identifies a connected gamepad. IDs are not reused within the lifetime of a [`Gamepads`] object, so a gamepad
+/// which is unplugged and plugged back in will receive a new ID.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct GamepadId(u32);
+
+/// An SDL-compatible device GUID, used to look up a gamepad's mapping. It's written as 32 hex digits.
+///
+/// On Linux, this encodes the device's bus type, vendor ID, product ID and version as little-endian 16-bit values at
+/// byte offsets 0, 4, 8 and 12 respectively.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Guid(pub [u8; 16]);
+
+impl Guid {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let mut guid = [0u8; 16];
+        if s.len() != 32 || !s.is_ascii() {
+            return None
+        }
+        for (i, byte) in guid.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+        }
+        Some(Self(guid))
+    }
+
+    /// Returns whether a database GUID matches a device's, ignoring the CRC field and treating a zero version as a
+    /// wildcard, as SDL does.
+    fn matches(&self, device: &Guid) -> bool {
+        let (a, b) = (&self.0, &device.0);
+        a[..2] == b[..2] && a[4..12] == b[4..12] && (a[12..] == b[12..] || a[12..] == [0; 4])
+    }
+}
+
+impl std::fmt::Display for Guid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl std::str::FromStr for Guid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::parse(s).ok_or(Error::Invalid)
+    }
+}
+
+/// An event from a gamepad.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum GamepadEvent {
+    /// A gamepad was connected. This is also sent for each gamepad which was already connected when the
+    /// [`Gamepads`] object was created.
+    Connected(GamepadId),
+
+    /// A gamepad was disconnected. Its ID is no longer valid.
+    Disconnected(GamepadId),
+
+    /// A button was pressed.
+    ButtonDown { id: GamepadId, button: Button },
+
+    /// A button was released.
+    ButtonUp { id: GamepadId, button: Button },
+
+    /// An axis moved to a new value.
+    AxisMotion { id: GamepadId, axis: Axis, value: f32 },
+}
+
+/// Describes one absolute axis of a raw device.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AxisInfo {
+    /// The axis' evdev code (`ABS_*`)
+    pub code: u16,
+    pub min: i32,
+    pub max: i32,
+}
+
+/// Describes a raw device, as reported by a [`DeviceSource`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeviceInfo {
+    pub name: String,
+    pub guid: Guid,
+    /// The evdev codes (`BTN_*`) of the device's buttons, in ascending order
+    pub buttons: Vec<u16>,
+    /// The device's absolute axes, in ascending order of code
+    pub axes: Vec<AxisInfo>,
+    /// Whether the device can [`rumble`](DeviceSource::rumble)
+    pub rumble: bool,
+}
+
+/// An event reported by a [`DeviceSource`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum RawEvent {
+    /// A device was added. `device` is chosen by the source, and must not be reused for another device.
+    Added { device: u32, info: DeviceInfo },
+
+    /// A device was removed.
+    Removed { device: u32 },
+
+    /// A device reported an evdev input event. Only `EV_KEY` and `EV_ABS` events are used, others are ignored.
+    Input { device: u32, kind: u16, code: u16, value: i32 },
+}
+
+/// A source of raw joystick devices and their input.
+///
+/// The system source is used by [`Gamepads::new`], but a custom one can be given to
+/// [`Gamepads::with_source`], for example to replay a recorded evdev event stream in tests.
+pub trait DeviceSource: Send {
+    /// Appends any device events which occurred since the last call. This must not block.
+    fn poll(&mut self, events: &mut Vec<RawEvent>);
+
+    /// Starts a rumble effect on a device, replacing any which is currently playing. Magnitudes are out of
+    /// [`u16::MAX`], and a zero duration stops the effect.
+    fn rumble(&mut self, device: u32, strong: u16, weak: u16, duration: Duration) -> Result<(), Error> {
+        let _ = (device, strong, weak, duration);
+        Err(Error::Unsupported)
+    }
+}
+
+struct Pad {
+    id: GamepadId,
+    device: u32,
+    info: DeviceInfo,
+    mapped: bool,
+    bindings: Vec<(Source, Target)>,
+    buttons: [bool; BUTTON_COUNT],
+    axes: [f32; AXIS_COUNT],
+    hats: [(i32, i32); 4],
+}
+
+/// The set of connected gamepads, and their state.
+pub struct Gamepads {
+    source: Box<dyn DeviceSource>,
+    mappings: Vec<Mapping>,
+    pads: Vec<Pad>,
+    next_id: u32,
+    raw: Vec<RawEvent>,
+    events: Vec<GamepadEvent>,
+}
+
+impl Gamepads {
+    /// Opens the system's gamepads. Any mappings in the `SDL_GAMECONTROLLERCONFIG` environment variable are loaded,
+    /// as they would be by SDL.
+    ///
+    /// Currently this is only implemented on Linux, where it reads the evdev devices in `/dev/input`. This requires
+    /// read access to them, which is normally granted to the logged-in user for joysticks. Other platforms will
+    /// return [`Error::Unsupported`].
+    pub fn new() -> Result<Self, Error> {
+        #[cfg(target_os = "linux")]
+        {
+            let mut gamepads = Self::with_source(evdev::EvdevSource::new()?);
+            if let Some(config) = std::env::var_os("SDL_GAMECONTROLLERCONFIG") {
+                let _ = gamepads.add_mappings(&config.to_string_lossy());
+            }
+            Ok(gamepads)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Err(Error::Unsupported)
+        }
+    }
+
+    /// Creates a set of gamepads fed by the given [`DeviceSource`].
+    pub fn with_source(source: impl DeviceSource + 'static) -> Self {
+        Self {
+            source: Box::new(source),
+            mappings: Vec::new(),
+            pads: Vec::new(),
+            next_id: 0,
+            raw: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Adds mappings in the format of SDL_GameControllerDB's `gamecontrollerdb.txt`, one per line, and returns how
+    /// many were added. Comments, blank lines, malformed lines and mappings for other platforms are skipped.
+    ///
+    /// Mappings added later take priority over earlier ones for the same device. Gamepads which are already
+    /// connected will use the new mappings from the next call to [`poll_events`](Self::poll_events).
+    pub fn add_mappings(&mut self, db: &str) -> usize {
+        let before = self.mappings.len();
+        self.mappings.extend(
+            db.lines().filter(|line| !line.trim_start().starts_with('#')).filter_map(Mapping::parse),
+        );
+        for pad in self.pads.iter_mut() {
+            let (mapped, bindings) = bind(&self.mappings, &pad.info);
+            pad.mapped = mapped;
+            pad.bindings = bindings;
+        }
+        self.mappings.len() - before
+    }
+
+    /// Returns an iterator of events currently in the buffer. The buffer must first be populated with
+    /// [`poll_events`](Self::poll_events), and stays the same until it's next called.
+    pub fn events(&self) -> impl IntoIterator<Item = &GamepadEvent> {
+        &self.events
+    }
+
+    /// Clears the event buffer and fills it with any events which have occurred since the last call, including
+    /// gamepads being connected or disconnected. This doesn't block.
+    pub fn poll_events(&mut self) {
+        self.events.clear();
+        self.source.poll(&mut self.raw);
+        for event in std::mem::take(&mut self.raw) {
+            self.process_event(event);
+        }
+    }
+
+    /// Returns the IDs of all connected gamepads.
+    pub fn connected(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.pads.iter().map(|pad| pad.id)
+    }
+
+    /// Returns the name of a gamepad, preferring the name given in its mapping, or `None` if it's disconnected.
+    pub fn name(&self, id: GamepadId) -> Option<&str> {
+        let pad = self.pad(id)?;
+        let mapping = self.mappings.iter().rev().find(|m| m.guid.matches(&pad.info.guid));
+        Some(mapping.map_or(&pad.info.name, |m| &m.name))
+    }
+
+    /// Returns a gamepad's GUID, which identifies its model for the purpose of mappings.
+    pub fn guid(&self, id: GamepadId) -> Option<Guid> {
+        self.pad(id).map(|pad| pad.info.guid)
+    }
+
+    /// Returns whether a gamepad has a mapping in the database. If it doesn't, the kernel's standard layout is used,
+    /// which may not be correct for older or unusual devices.
+    pub fn is_mapped(&self, id: GamepadId) -> bool {
+        self.pad(id).map_or(false, |pad| pad.mapped)
+    }
+
+    /// Returns whether a button is held, as of the last call to [`poll_events`](Self::poll_events).
+    pub fn button(&self, id: GamepadId, button: Button) -> bool {
+        self.pad(id).map_or(false, |pad| pad.buttons[button as usize])
+    }
+
+    /// Returns the value of an axis, as of the last call to [`poll_events`](Self::poll_events).
+    pub fn axis(&self, id: GamepadId, axis: Axis) -> f32 {
+        self.pad(id).map_or(0.0, |pad| pad.axes[axis as usize])
+    }
+
+    /// Rumbles a gamepad for the given duration. `strong` and `weak` are the magnitudes, from 0.0 to 1.0, of the
+    /// low-frequency (left) and high-frequency (right) motors. Calling this again replaces the current effect, so it
+    /// can be stopped early by passing zero magnitudes.
+    ///
+    /// Returns [`Error::Unsupported`] if the gamepad can't rumble, or [`Error::Invalid`] if it's disconnected.
+    pub fn rumble(&mut self, id: GamepadId, strong: f32, weak: f32, duration: Duration) -> Result<(), Error> {
+        let pad = self.pad(id).ok_or(Error::Invalid)?;
+        if !pad.info.rumble {
+            return Err(Error::Unsupported)
+        }
+        let magnitude = |x: f32| (x.clamp(0.0, 1.0) * f32::from(u16::MAX)) as u16;
+        self.source.rumble(pad.device, magnitude(strong), magnitude(weak), duration)
+    }
+
+    fn pad(&self, id: GamepadId) -> Option<&Pad> {
+        self.pads.iter().find(|pad| pad.id == id)
+    }
+
+    fn process_event(&mut self, event: RawEvent) {
+        match event {
+            RawEvent::Added { device, info } => {
+                let id = GamepadId(self.next_id);
+                self.next_id = self.next_id.wrapping_add(1);
+                let (mapped, bindings) = bind(&self.mappings, &info);
+                self.pads.push(Pad {
+                    id,
+                    device,
+                    info,
+                    mapped,
+                    bindings,
+                    buttons: [false; BUTTON_COUNT],
+                    axes: [0.0; AXIS_COUNT],
+                    hats: [(0, 0); 4],
+                });
+                self.events.push(GamepadEvent::Connected(id));
+            },
+            RawEvent::Removed { device } => {
+                if let Some(i) = self.pads.iter().position(|pad| pad.device == device) {
+                    let pad = self.pads.remove(i);
+                    self.events.push(GamepadEvent::Disconnected(pad.id));
+                }
+            },
+            RawEvent::Input { device, kind, code, value } => {
+                if let Some(pad) = self.pads.iter_mut().find(|pad| pad.device == device) {
+                    pad.process_input(kind, code, value, &mut self.events);
+                }
+            },
+        }
+    }
+}
+
+impl Pad {
+    fn process_input(&mut self, kind: u16, code: u16, value: i32, events: &mut Vec<GamepadEvent>) {
+        let hat = (0..4u16).find(|&i| {
+            let (x, y) = mapping::hat_axes(i);
+            code == x || code == y
+        });
+        if kind == EV_ABS {
+            if let Some(index) = hat {
+                let hat = &mut self.hats[usize::from(index)];
+                if code == mapping::hat_axes(index).0 {
+                    hat.0 = value;
+                } else {
+                    hat.1 = value;
+                }
+            }
+        }
+
+        for i in 0..self.bindings.len() {
+            let (source, target) = self.bindings[i];
+            // `value` is in the range -1..1 for a full axis, or 0..1 for anything else
+            let (value, full) = match source {
+                Source::Key(key) if kind == EV_KEY && key == code => (if value != 0 { 1.0 } else { 0.0 }, false),
+                Source::Abs { code: abs, range, invert } if kind == EV_ABS && abs == code => {
+                    let info = match self.info.axes.iter().find(|a| a.code == code) {
+                        Some(info) => info,
+                        None => continue,
+                    };
+                    let span = (info.max - info.min).max(1) as f32;
+                    let norm = ((value - info.min) as f32 / span * 2.0 - 1.0).clamp(-1.0, 1.0);
+                    match (range, invert) {
+                        (Range::Full, false) => (norm, true),
+                        (Range::Full, true) => (-norm, true),
+                        (Range::Positive, _) => (norm.max(0.0), false),
+                        (Range::Negative, _) => ((-norm).max(0.0), false),
+                    }
+                },
+                Source::Hat { index, mask } if kind == EV_ABS && hat == Some(index) => {
+                    let (x, y) = self.hats[usize::from(index)];
+                    let state = (y < 0) as u8 | ((x > 0) as u8) << 1 | ((y > 0) as u8) << 2 | ((x < 0) as u8) << 3;
+                    (if state & mask != 0 { 1.0 } else { 0.0 }, false)
+                },
+                _ => continue,
+            };
+            self.apply(target, value, full, events);
+        }
+    }
+
+    fn apply(&mut self, target: Target, value: f32, full: bool, events: &mut Vec<GamepadEvent>) {
+        let id = self.id;
+        match target {
+            Target::Button(button) => {
+                let down = value > 0.5;
+                if self.buttons[button as usize] != down {
+                    self.buttons[button as usize] = down;
+                    events.push(if down {
+                        GamepadEvent::ButtonDown { id, button }
+                    } else {
+                        GamepadEvent::ButtonUp { id, button }
+                    });
+                }
+            },
+            Target::Axis(axis, range) => {
+                let trigger = matches!(axis, Axis::LeftTrigger | Axis::RightTrigger);
+                let half = if full { (value + 1.0) / 2.0 } else { value };
+                let value = match range {
+                    Range::Full if trigger => half,
+                    Range::Full => value,
+                    Range::Positive => half,
+                    Range::Negative => -half,
+                };
+                if self.axes[axis as usize] != value {
+                    self.axes[axis as usize] = value;
+                    events.push(GamepadEvent::AxisMotion { id, axis, value });
+                }
+            },
+        }
+    }
+}
+
+/// Finds the bindings for a device, returning whether they came from a database mapping.
+fn bind(mappings: &[Mapping], info: &DeviceInfo) -> (bool, Vec<(Source, Target)>) {
+    match mappings.iter().rev().find(|m| m.guid.matches(&info.guid)) {
+        Some(mapping) => (true, mapping.resolve(info)),
+        None => (false, mapping::default_bindings(info)),
+    }
+}
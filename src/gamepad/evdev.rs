@@ -0,0 +1,347 @@
+//! Device source reading the Linux kernel's evdev interface, as used by SDL.
+
+use super::{AxisInfo, DeviceInfo, DeviceSource, Guid, RawEvent, EV_ABS, EV_KEY};
+use crate::error::Error;
+use libc::c_int;
+use std::{ffi::CString, fs, mem, ptr, time::Duration};
+
+const INPUT_DIR: &str = "/dev/input";
+
+const EV_SYN: u16 = 0x00;
+const EV_FF: u16 = 0x15;
+const EV_MAX: u16 = 0x1f;
+const SYN_REPORT: u16 = 0x00;
+const SYN_DROPPED: u16 = 0x03;
+const KEY_MAX: u16 = 0x2ff;
+const ABS_MAX: u16 = 0x3f;
+const FF_RUMBLE: u16 = 0x50;
+const FF_MAX: u16 = 0x7f;
+const BTN_MISC: u16 = 0x100;
+const BTN_JOYSTICK: u16 = 0x120;
+
+// ioctl request numbers, encoded as by the _IOC macro in <asm-generic/ioctl.h>
+const fn evioc(read: bool, nr: u32, size: usize) -> u32 {
+    (if read { 2 } else { 1 }) << 30 | (size as u32) << 16 | (b'E' as u32) << 8 | nr
+}
+const EVIOCGID: u32 = evioc(true, 0x02, mem::size_of::<libc::input_id>());
+const fn eviocgname(len: usize) -> u32 {
+    evioc(true, 0x06, len)
+}
+const fn eviocgkey(len: usize) -> u32 {
+    evioc(true, 0x18, len)
+}
+const fn eviocgbit(ev: u16, len: usize) -> u32 {
+    evioc(true, 0x20 + ev as u32, len)
+}
+const fn eviocgabs(abs: u16) -> u32 {
+    evioc(true, 0x40 + abs as u32, mem::size_of::<libc::input_absinfo>())
+}
+const EVIOCSFF: u32 = evioc(false, 0x80, mem::size_of::<libc::ff_effect>());
+
+fn os_error() -> Error {
+    Error::Text(std::io::Error::last_os_error().to_string().into())
+}
+
+fn test_bit(bits: &[u8], code: u16) -> bool {
+    bits.get(usize::from(code / 8)).map_or(false, |byte| byte & (1 << (code % 8)) != 0)
+}
+
+/// Returns whether a device looks like a gamepad or joystick rather than some other device with buttons and axes,
+/// such as a keyboard, mouse, touchpad or a gamepad's separate motion sensor device.
+fn is_joystick(buttons: &[u16], axes: &[AxisInfo]) -> bool {
+    let has_joystick_button = buttons.iter().any(|&c| (BTN_JOYSTICK..BTN_JOYSTICK + 0x20).contains(&c));
+    let has_misc_button = buttons.iter().any(|&c| (BTN_MISC..BTN_MISC + 0x10).contains(&c));
+    let has_stick = axes.iter().any(|a| a.code == 0x00) && axes.iter().any(|a| a.code == 0x01);
+    has_joystick_button || (has_misc_button && has_stick)
+}
+
+struct Device {
+    id: u32,
+    node: String,
+    fd: c_int,
+    buttons: Vec<u16>,
+    axes: Vec<u16>,
+    effect: i16,
+    dropped: bool,
+}
+
+impl Device {
+    /// Opens `/dev/input/<node>`, returning `None` if it can't be opened or isn't a joystick.
+    fn open(node: &str, id: u32) -> Option<(Self, DeviceInfo)> {
+        let path = CString::new(format!("{}/{}", INPUT_DIR, node)).ok()?;
+        // Write access is only needed for rumble, so fall back to read-only
+        let mut writable = true;
+        let mut fd = unsafe { libc::open(path.as_ptr(), libc::O_RDWR | libc::O_NONBLOCK | libc::O_CLOEXEC) };
+        if fd < 0 {
+            writable = false;
+            fd = unsafe { libc::open(path.as_ptr(), libc::O_RDONLY | libc::O_NONBLOCK | libc::O_CLOEXEC) };
+        }
+        if fd < 0 {
+            return None
+        }
+        let mut device =
+            Self { id, node: node.to_owned(), fd, buttons: Vec::new(), axes: Vec::new(), effect: -1, dropped: false };
+
+        let ev_bits = device.bits(0, EV_MAX)?;
+        if !test_bit(&ev_bits, EV_KEY) {
+            return None
+        }
+        let key_bits = device.bits(EV_KEY, KEY_MAX)?;
+        let buttons = (0..KEY_MAX).filter(|&c| test_bit(&key_bits, c)).collect::<Vec<_>>();
+        let mut axes = Vec::new();
+        if test_bit(&ev_bits, EV_ABS) {
+            let abs_bits = device.bits(EV_ABS, ABS_MAX)?;
+            for code in (0..ABS_MAX).filter(|&c| test_bit(&abs_bits, c)) {
+                let abs = device.absinfo(code)?;
+                axes.push(AxisInfo { code, min: abs.minimum, max: abs.maximum });
+            }
+        }
+        if !is_joystick(&buttons, &axes) {
+            return None
+        }
+        let rumble = writable
+            && test_bit(&ev_bits, EV_FF)
+            && device.bits(EV_FF, FF_MAX).map_or(false, |bits| test_bit(&bits, FF_RUMBLE));
+
+        let mut input_id: libc::input_id = unsafe { mem::zeroed() };
+        let mut name = [0u8; 128];
+        unsafe {
+            if libc::ioctl(fd, EVIOCGID as _, &mut input_id) < 0 {
+                return None
+            }
+            let _ = libc::ioctl(fd, eviocgname(name.len() - 1) as _, name.as_mut_ptr());
+        }
+        let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        let name = String::from_utf8_lossy(&name[..name_len]).into_owned();
+
+        // SDL falls back to identifying the device by name if it has no vendor and product IDs
+        let mut guid = [0u8; 16];
+        guid[..2].copy_from_slice(&input_id.bustype.to_le_bytes());
+        if input_id.vendor != 0 && input_id.product != 0 {
+            guid[4..6].copy_from_slice(&input_id.vendor.to_le_bytes());
+            guid[8..10].copy_from_slice(&input_id.product.to_le_bytes());
+            guid[12..14].copy_from_slice(&input_id.version.to_le_bytes());
+        } else {
+            let len = name.len().min(12);
+            guid[4..4 + len].copy_from_slice(&name.as_bytes()[..len]);
+        }
+
+        device.buttons = buttons.clone();
+        device.axes = axes.iter().map(|a| a.code).collect();
+        Some((device, DeviceInfo { name, guid: Guid(guid), buttons, axes, rumble }))
+    }
+
+    fn bits(&self, ev: u16, max: u16) -> Option<Vec<u8>> {
+        let mut bits = vec![0u8; usize::from(max / 8 + 1)];
+        unsafe {
+            if libc::ioctl(self.fd, eviocgbit(ev, bits.len()) as _, bits.as_mut_ptr()) < 0 {
+                return None
+            }
+        }
+        Some(bits)
+    }
+
+    fn absinfo(&self, code: u16) -> Option<libc::input_absinfo> {
+        unsafe {
+            let mut abs: libc::input_absinfo = mem::zeroed();
+            (libc::ioctl(self.fd, eviocgabs(code) as _, &mut abs) >= 0).then(|| abs)
+        }
+    }
+
+    /// Reports the current state of every button and axis, for when the device is opened or the kernel has dropped
+    /// events because its buffer overflowed.
+    fn sync(&self, events: &mut Vec<RawEvent>) {
+        let mut keys = [0u8; KEY_MAX as usize / 8 + 1];
+        unsafe {
+            if libc::ioctl(self.fd, eviocgkey(keys.len()) as _, keys.as_mut_ptr()) < 0 {
+                return
+            }
+        }
+        for &code in self.buttons.iter() {
+            let value = test_bit(&keys, code).into();
+            events.push(RawEvent::Input { device: self.id, kind: EV_KEY, code, value });
+        }
+        for &code in self.axes.iter() {
+            if let Some(abs) = self.absinfo(code) {
+                events.push(RawEvent::Input { device: self.id, kind: EV_ABS, code, value: abs.value });
+            }
+        }
+    }
+
+    /// Reads any pending input, returning `false` if the device has gone away.
+    fn read(&mut self, events: &mut Vec<RawEvent>) -> bool {
+        let mut buf: [libc::input_event; 64] = unsafe { mem::zeroed() };
+        loop {
+            let len = unsafe { libc::read(self.fd, buf.as_mut_ptr().cast(), mem::size_of_val(&buf)) };
+            if len < 0 {
+                let err = std::io::Error::last_os_error().raw_os_error();
+                return err == Some(libc::EAGAIN) || err == Some(libc::EINTR)
+            }
+            let count = len as usize / mem::size_of::<libc::input_event>();
+            for event in &buf[..count] {
+                match (event.type_, event.code) {
+                    (EV_SYN, SYN_DROPPED) => self.dropped = true,
+                    // Any partial state before the report which follows a drop is discarded
+                    (EV_SYN, SYN_REPORT) if self.dropped => {
+                        self.dropped = false;
+                        self.sync(events);
+                    },
+                    (EV_KEY, code) | (EV_ABS, code) if !self.dropped => events.push(RawEvent::Input {
+                        device: self.id,
+                        kind: event.type_,
+                        code,
+                        value: event.value,
+                    }),
+                    _ => (),
+                }
+            }
+            if count < buf.len() {
+                return len != 0
+            }
+        }
+    }
+
+    fn write_ff(&self, code: u16, value: i32) -> Result<(), Error> {
+        let mut event: libc::input_event = unsafe { mem::zeroed() };
+        event.type_ = EV_FF;
+        event.code = code;
+        event.value = value;
+        let size = mem::size_of_val(&event);
+        match unsafe { libc::write(self.fd, (&event as *const libc::input_event).cast(), size) } {
+            n if n == size as isize => Ok(()),
+            _ => Err(os_error()),
+        }
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = libc::close(self.fd);
+        }
+    }
+}
+
+/// Reads joysticks from `/dev/input/event*`, watching the directory with inotify for hotplug.
+pub(super) struct EvdevSource {
+    inotify: c_int,
+    devices: Vec<Device>,
+    next_id: u32,
+    pending: Vec<RawEvent>,
+}
+
+impl EvdevSource {
+    pub(super) fn new() -> Result<Self, Error> {
+        // Without inotify (or `/dev/input`) there's no hotplug, but that isn't worth failing over
+        let inotify = unsafe {
+            let fd = libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC);
+            let dir = CString::new(INPUT_DIR).unwrap();
+            if fd >= 0 && libc::inotify_add_watch(fd, dir.as_ptr(), libc::IN_CREATE | libc::IN_ATTRIB) < 0 {
+                let _ = libc::close(fd);
+                -1
+            } else {
+                fd
+            }
+        };
+        let mut source = Self { inotify, devices: Vec::new(), next_id: 0, pending: Vec::new() };
+
+        let mut nodes = fs::read_dir(INPUT_DIR)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter_map(|name| Some((name.strip_prefix("event")?.parse::<u32>().ok()?, name)))
+            .collect::<Vec<_>>();
+        nodes.sort_unstable();
+        for (_, node) in nodes {
+            source.open(&node);
+        }
+        Ok(source)
+    }
+
+    fn open(&mut self, node: &str) {
+        if !node.starts_with("event") || self.devices.iter().any(|d| d.node == node) {
+            return
+        }
+        if let Some((device, info)) = Device::open(node, self.next_id) {
+            self.next_id = self.next_id.wrapping_add(1);
+            self.pending.push(RawEvent::Added { device: device.id, info });
+            device.sync(&mut self.pending);
+            self.devices.push(device);
+        }
+    }
+
+    fn read_inotify(&mut self) {
+        const HEADER: usize = mem::size_of::<libc::inotify_event>();
+        let mut buf = [0u8; 4096];
+        loop {
+            let len = unsafe { libc::read(self.inotify, buf.as_mut_ptr().cast(), buf.len()) };
+            if len <= 0 {
+                break
+            }
+            let mut offset = 0;
+            while offset + HEADER <= len as usize {
+                let event = unsafe { ptr::read_unaligned(buf[offset..].as_ptr().cast::<libc::inotify_event>()) };
+                let name = &buf[offset + HEADER..offset + HEADER + event.len as usize];
+                let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+                // New nodes often aren't accessible until udev sets their permissions, hence also watching IN_ATTRIB
+                if let Ok(name) = std::str::from_utf8(name) {
+                    self.open(name);
+                }
+                offset += HEADER + event.len as usize;
+            }
+        }
+    }
+}
+
+impl DeviceSource for EvdevSource {
+    fn poll(&mut self, events: &mut Vec<RawEvent>) {
+        if self.inotify >= 0 {
+            self.read_inotify();
+        }
+        events.append(&mut self.pending);
+
+        let mut i = 0;
+        while i < self.devices.len() {
+            if self.devices[i].read(events) {
+                i += 1;
+            } else {
+                let device = self.devices.remove(i);
+                events.push(RawEvent::Removed { device: device.id });
+            }
+        }
+    }
+
+    fn rumble(&mut self, device: u32, strong: u16, weak: u16, duration: Duration) -> Result<(), Error> {
+        let device = self.devices.iter_mut().find(|d| d.id == device).ok_or(Error::Invalid)?;
+        if duration.as_millis() == 0 || (strong == 0 && weak == 0) {
+            return match device.effect {
+                -1 => Ok(()),
+                effect => device.write_ff(effect as u16, 0),
+            }
+        }
+
+        let mut effect: libc::ff_effect = unsafe { mem::zeroed() };
+        effect.type_ = FF_RUMBLE;
+        effect.id = device.effect;
+        effect.replay.length = duration.as_millis().min(u16::MAX.into()) as u16;
+        unsafe {
+            let rumble = libc::ff_rumble_effect { strong_magnitude: strong, weak_magnitude: weak };
+            ptr::write(effect.u.as_mut_ptr().cast(), rumble);
+            if libc::ioctl(device.fd, EVIOCSFF as _, &mut effect) < 0 {
+                return Err(os_error())
+            }
+        }
+        device.effect = effect.id;
+        device.write_ff(effect.id as u16, 1)
+    }
+}
+
+impl Drop for EvdevSource {
+    fn drop(&mut self) {
+        if self.inotify >= 0 {
+            unsafe {
+                let _ = libc::close(self.inotify);
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Parser and resolver for SDL_GameControllerDB mapping strings.
+//!
+//! A mapping line looks like `GUID,Name,a:b0,b:b1,leftx:a0,dpup:h0.1,-lefty:-a1,righttrigger:a5~,platform:Linux,`.
+//! The button, axis and hat indices it refers to are SDL's joystick indices, which are assigned in evdev code order
+//! (see [`Binding::resolve`]), so a parsed [`Mapping`] has to be resolved against a device before it can be used.
+
+use super::{Axis, Button, DeviceInfo, Guid};
+
+const BTN_JOYSTICK: u16 = 0x120;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT3Y: u16 = 0x17;
+
+/// Which part of an axis' range a binding covers.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) enum Range {
+    Full,
+    Positive,
+    Negative,
+}
+
+/// The output side of a binding.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) enum Target {
+    Button(Button),
+    Axis(Axis, Range),
+}
+
+/// The input side of a binding, in terms of SDL joystick indices.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Input {
+    Button(u16),
+    Axis { index: u16, range: Range, invert: bool },
+    Hat { index: u16, mask: u8 },
+}
+
+/// The input side of a binding, in terms of evdev codes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) enum Source {
+    Key(u16),
+    Abs { code: u16, range: Range, invert: bool },
+    Hat { index: u16, mask: u8 },
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Binding {
+    input: Input,
+    target: Target,
+}
+
+impl Binding {
+    /// Translates SDL's joystick indices into evdev codes for the given device.
+    ///
+    /// SDL numbers a Linux joystick's buttons starting with the codes from `BTN_JOYSTICK` upwards, followed by any
+    /// below it, numbers its axes in code order skipping the hat axes, and numbers hats in order among the ones the
+    /// device actually has.
+    fn resolve(&self, info: &DeviceInfo) -> Option<(Source, Target)> {
+        let source = match self.input {
+            Input::Button(index) => {
+                let high = info.buttons.iter().copied().filter(|&c| c >= BTN_JOYSTICK);
+                let low = info.buttons.iter().copied().filter(|&c| c < BTN_JOYSTICK);
+                Source::Key(high.chain(low).nth(index.into())?)
+            },
+            Input::Axis { index, range, invert } => {
+                let code = info
+                    .axes
+                    .iter()
+                    .map(|a| a.code)
+                    .filter(|c| !(ABS_HAT0X..=ABS_HAT3Y).contains(c))
+                    .nth(index.into())?;
+                Source::Abs { code, range, invert }
+            },
+            Input::Hat { index, mask } => {
+                let index = (0..4)
+                    .filter(|&hat| {
+                        let (x, y) = hat_axes(hat);
+                        info.axes.iter().any(|a| a.code == x || a.code == y)
+                    })
+                    .nth(index.into())?;
+                Source::Hat { index, mask }
+            },
+        };
+        Some((source, self.target))
+    }
+}
+
+/// A parsed mapping from the database.
+#[derive(Clone, Debug)]
+pub(super) struct Mapping {
+    pub(super) guid: Guid,
+    pub(super) name: String,
+    bindings: Vec<Binding>,
+}
+
+impl Mapping {
+    /// Parses a single mapping line, returning `None` if it's malformed or meant for a different platform.
+    pub(super) fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.trim().split(',');
+        let guid = Guid::parse(fields.next()?)?;
+        let name = fields.next()?.to_owned();
+        let mut bindings = Vec::new();
+        for field in fields.filter(|f| !f.is_empty()) {
+            let (key, value) = field.split_once(':')?;
+            if key == "platform" {
+                if value != PLATFORM {
+                    return None
+                }
+                continue
+            }
+            if let (Some(target), Some(input)) = (parse_target(key), parse_input(value)) {
+                bindings.push(Binding { input, target });
+            }
+        }
+        Some(Self { guid, name, bindings })
+    }
+
+    /// Resolves this mapping against a device, dropping any bindings to inputs it doesn't have.
+    pub(super) fn resolve(&self, info: &DeviceInfo) -> Vec<(Source, Target)> {
+        self.bindings.iter().filter_map(|b| b.resolve(info)).collect()
+    }
+}
+
+/// Builds bindings for a device without a database entry, following the kernel's gamepad specification
+/// (`Documentation/input/gamepad.rst`), which most modern drivers adhere to.
+pub(super) fn default_bindings(info: &DeviceInfo) -> Vec<(Source, Target)> {
+    const KEYS: &[(u16, Target)] = &[
+        (0x130, Target::Button(Button::A)),
+        (0x131, Target::Button(Button::B)),
+        (0x133, Target::Button(Button::X)),
+        (0x134, Target::Button(Button::Y)),
+        (0x136, Target::Button(Button::LeftShoulder)),
+        (0x137, Target::Button(Button::RightShoulder)),
+        (0x138, Target::Axis(Axis::LeftTrigger, Range::Full)),
+        (0x139, Target::Axis(Axis::RightTrigger, Range::Full)),
+        (0x13a, Target::Button(Button::Back)),
+        (0x13b, Target::Button(Button::Start)),
+        (0x13c, Target::Button(Button::Guide)),
+        (0x13d, Target::Button(Button::LeftStick)),
+        (0x13e, Target::Button(Button::RightStick)),
+        (0x220, Target::Button(Button::DPadUp)),
+        (0x221, Target::Button(Button::DPadDown)),
+        (0x222, Target::Button(Button::DPadLeft)),
+        (0x223, Target::Button(Button::DPadRight)),
+    ];
+    const AXES: &[(u16, Axis)] = &[
+        (0x00, Axis::LeftX),
+        (0x01, Axis::LeftY),
+        (0x02, Axis::LeftTrigger),
+        (0x03, Axis::RightX),
+        (0x04, Axis::RightY),
+        (0x05, Axis::RightTrigger),
+    ];
+    const HAT: &[(u8, Button)] = &[
+        (1, Button::DPadUp),
+        (2, Button::DPadRight),
+        (4, Button::DPadDown),
+        (8, Button::DPadLeft),
+    ];
+
+    let mut bindings = Vec::new();
+    for &(code, target) in KEYS {
+        if info.buttons.contains(&code) {
+            bindings.push((Source::Key(code), target));
+        }
+    }
+    for &(code, axis) in AXES {
+        if info.axes.iter().any(|a| a.code == code) {
+            let source = Source::Abs { code, range: Range::Full, invert: false };
+            bindings.push((source, Target::Axis(axis, Range::Full)));
+        }
+    }
+    if info.axes.iter().any(|a| a.code == ABS_HAT0X) {
+        for &(mask, button) in HAT {
+            bindings.push((Source::Hat { index: 0, mask }, Target::Button(button)));
+        }
+    }
+    bindings
+}
+
+/// Returns the evdev codes of a hat's X and Y axes.
+pub(super) fn hat_axes(index: u16) -> (u16, u16) {
+    (ABS_HAT0X + index * 2, ABS_HAT0X + index * 2 + 1)
+}
+
+#[cfg(target_os = "linux")]
+const PLATFORM: &str = "Linux";
+#[cfg(target_os = "windows")]
+const PLATFORM: &str = "Windows";
+#[cfg(target_os = "macos")]
+const PLATFORM: &str = "Mac OS X";
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+const PLATFORM: &str = "";
+
+fn parse_target(key: &str) -> Option<Target> {
+    let (range, key) = match key.as_bytes().first() {
+        Some(b'+') => (Range::Positive, &key[1..]),
+        Some(b'-') => (Range::Negative, &key[1..]),
+        _ => (Range::Full, key),
+    };
+    let axis = match key {
+        "leftx" => Axis::LeftX,
+        "lefty" => Axis::LeftY,
+        "rightx" => Axis::RightX,
+        "righty" => Axis::RightY,
+        "lefttrigger" => Axis::LeftTrigger,
+        "righttrigger" => Axis::RightTrigger,
+        _ => {
+            let button = match key {
+                "a" => Button::A,
+                "b" => Button::B,
+                "x" => Button::X,
+                "y" => Button::Y,
+                "back" => Button::Back,
+                "guide" => Button::Guide,
+                "start" => Button::Start,
+                "leftstick" => Button::LeftStick,
+                "rightstick" => Button::RightStick,
+                "leftshoulder" => Button::LeftShoulder,
+                "rightshoulder" => Button::RightShoulder,
+                "dpup" => Button::DPadUp,
+                "dpdown" => Button::DPadDown,
+                "dpleft" => Button::DPadLeft,
+                "dpright" => Button::DPadRight,
+                "misc1" => Button::Misc1,
+                "paddle1" => Button::Paddle1,
+                "paddle2" => Button::Paddle2,
+                "paddle3" => Button::Paddle3,
+                "paddle4" => Button::Paddle4,
+                "touchpad" => Button::Touchpad,
+                _ => return None,
+            };
+            return (range == Range::Full).then(|| Target::Button(button))
+        },
+    };
+    Some(Target::Axis(axis, range))
+}
+
+fn parse_input(value: &str) -> Option<Input> {
+    let (value, invert) = match value.strip_suffix('~') {
+        Some(v) => (v, true),
+        None => (value, false),
+    };
+    let (range, value) = match value.as_bytes().first() {
+        Some(b'+') => (Range::Positive, &value[1..]),
+        Some(b'-') => (Range::Negative, &value[1..]),
+        _ => (Range::Full, value),
+    };
+    if let Some(index) = value.strip_prefix('a') {
+        Some(Input::Axis { index: index.parse().ok()?, range, invert })
+    } else if let Some(index) = value.strip_prefix('b') {
+        Some(Input::Button(index.parse().ok()?))
+    } else if let Some(hat) = value.strip_prefix('h') {
+        let (index, mask) = hat.split_once('.')?;
+        Some(Input::Hat { index: index.parse().ok()?, mask: mask.parse().ok()? })
+    } else {
+        None
+    }
+}
@@ -1,3 +1,5 @@
+use crate::{error::Error, event::Event};
+
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Key {
     // common
@@ -113,6 +115,295 @@ pub enum Key {
     LaunchMail, // what the fuck?
     LaunchMediaSelect,
     Play,
+
+    /// A key which doesn't have a variant here, identified by the platform's own code for it.
+    /// On Linux this is the X11 keysym, and on Windows it's the virtual-key code.
+    Unknown(u32),
+}
+
+// Generates `Key::name` and the list of keys `FromStr` searches through. `name` is an exhaustive match,
+// so a variant which is missing here is a compile error rather than a key that can't be parsed.
+macro_rules! key_names {
+    ($($key:ident),* $(,)?) => {
+        impl Key {
+            const NAMED: &'static [Key] = &[$(Key::$key),*];
+
+            fn name(self) -> &'static str {
+                match self {
+                    $(Key::$key => stringify!($key),)*
+                    Key::Unknown(_) => "Unknown",
+                }
+            }
+        }
+    };
+}
+
+key_names! {
+    LeftAlt, RightAlt, Applications, Backspace, CapsLock, LeftControl, RightControl, Delete, End, Escape, Home,
+    Insert, NumLock, PageDown, PageUp, Pause, PrintScreen, Return, ScrollLock, Sleep, Space, LeftShift, RightShift,
+    LeftSuper, RightSuper, Tab,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Alpha0, Alpha1, Alpha2, Alpha3, Alpha4, Alpha5, Alpha6, Alpha7, Alpha8, Alpha9,
+    Minus, Plus, Equals, Period, Comma, Hash, BracketLeft, BracketRight, BraceLeft, BraceRight,
+    Grave, Apostrophe, Semicolon, Colon, Underscore, Pipe, Slash, Backslash, QuestionMark,
+    LessThan, GreaterThan, Quote,
+    KeypadAdd, KeypadSubtract, KeypadMultiply, KeypadDivide, KeypadDecimal, KeypadSeparator,
+    Keypad0, Keypad1, Keypad2, Keypad3, Keypad4, Keypad5, Keypad6, Keypad7, Keypad8, Keypad9,
+    LeftArrow, RightArrow, UpArrow, DownArrow,
+    MediaPreviousTrack, MediaNextTrack, MediaPlayPause, MediaStop, MediaVolumeDown, MediaVolumeUp, MediaVolumeMute,
+    Attn, Clear, CrSel, EraseEof, Execute, ExSel, OemReset, OemJump, Oem102, OemPa1, OemPa2, OemPa3, OemWsCtrl,
+    OemClear, OemCuSel, OemAttn, OemFinish, OemCopy, OemAuto, OemEnlw, OemBackTab, Pa1, Print, Select, Zoom,
+    ImeAccept, ImeConvert, ImeNonConvert, ImeFinal, ImeModeChangeRequest, ImeProcess, ImeOn, ImeOff,
+    ImeKanaOrHangul, ImeHanjaOrKanji, ImeJunja,
+    BrowserBack, BrowserFavourites, BrowserForward, BrowserHome, BrowserRefresh, BrowserSearch, BrowserStop, Help,
+    LaunchApplication1, LaunchApplication2, LaunchMail, LaunchMediaSelect, Play,
+}
+
+impl Key {
+    // Other common names for keys, which are accepted by `FromStr` but never produced by `Display`
+    const ALIASES: [(&'static str, Key); 40] = [
+        ("Esc", Key::Escape),
+        ("Enter", Key::Return),
+        ("PgUp", Key::PageUp),
+        ("PgDn", Key::PageDown),
+        ("PgDown", Key::PageDown),
+        ("Del", Key::Delete),
+        ("Ins", Key::Insert),
+        ("Bksp", Key::Backspace),
+        ("PrtSc", Key::PrintScreen),
+        ("Menu", Key::Applications),
+        ("Left", Key::LeftArrow),
+        ("Right", Key::RightArrow),
+        ("Up", Key::UpArrow),
+        ("Down", Key::DownArrow),
+        ("Shift", Key::LeftShift),
+        ("Ctrl", Key::LeftControl),
+        ("Control", Key::LeftControl),
+        ("Alt", Key::LeftAlt),
+        ("Super", Key::LeftSuper),
+        ("Win", Key::LeftSuper),
+        ("0", Key::Alpha0),
+        ("1", Key::Alpha1),
+        ("2", Key::Alpha2),
+        ("3", Key::Alpha3),
+        ("4", Key::Alpha4),
+        ("5", Key::Alpha5),
+        ("6", Key::Alpha6),
+        ("7", Key::Alpha7),
+        ("8", Key::Alpha8),
+        ("9", Key::Alpha9),
+        ("-", Key::Minus),
+        ("+", Key::Plus),
+        ("=", Key::Equals),
+        (".", Key::Period),
+        (",", Key::Comma),
+        ("/", Key::Slash),
+        ("\\", Key::Backslash),
+        (";", Key::Semicolon),
+        ("[", Key::BracketLeft),
+        ("]", Key::BracketRight),
+    ];
+}
+
+/// Formats the key as its name, which is the same as its variant name, for example `PageUp` or `Alpha1`.
+/// [`Key::Unknown`] is formatted as `Unknown(0x...)` with its code in hexadecimal.
+/// 
+/// These names are stable, and can be parsed back with [`str::parse`].
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Key::Unknown(code) => write!(f, "Unknown({:#x})", code),
+            key => f.write_str(key.name()),
+        }
+    }
+}
+
+/// Parses a key from its name as formatted by [`Display`](std::fmt::Display), or from a common alias such as
+/// `Esc`, `Enter`, `PgUp` or `1`. Names are not case-sensitive.
+impl std::str::FromStr for Key {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let s = s.trim();
+        if let Some(&key) = Self::NAMED.iter().find(|key| key.name().eq_ignore_ascii_case(s)) {
+            return Ok(key)
+        }
+        if let Some(&(_, key)) = Self::ALIASES.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)) {
+            return Ok(key)
+        }
+        parse_code(s, "Unknown").map(Key::Unknown).ok_or(Error::Invalid)
+    }
+}
+
+// Parses `Name(123)` or `Name(0x7B)`, as used for the catch-all variants
+fn parse_code<T: TryFrom<u32>>(s: &str, name: &str) -> Option<T> {
+    let prefix = s.get(..name.len() + 1)?;
+    if !prefix[..name.len()].eq_ignore_ascii_case(name) || !prefix.ends_with('(') {
+        return None
+    }
+    let code = s[name.len() + 1..].strip_suffix(')')?.trim();
+    let code = match code.strip_prefix("0x").or_else(|| code.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => code.parse().ok()?,
+    };
+    T::try_from(code).ok()
+}
+
+/// Identifies a physical key by its position on the keyboard, regardless of the keyboard layout.
+/// 
+/// For example, the key to the right of Tab is always the same `ScanCode`, whether it types Q on QWERTY or A on
+/// AZERTY. This makes it the right choice for things like WASD movement, where the position matters more than the
+/// label.
+/// 
+/// The numbering follows the Linux evdev key codes (`KEY_*` in `linux/input-event-codes.h`) on every platform.
+/// A value of zero means the platform didn't say which key it was.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ScanCode(u32);
+
+impl ScanCode {
+    /// Creates a `ScanCode` from a Linux evdev key code.
+    pub const fn from_evdev(code: u32) -> Self {
+        Self(code)
+    }
+
+    /// Returns the Linux evdev key code for this `ScanCode`.
+    pub const fn to_evdev(self) -> u32 {
+        self.0
+    }
+
+    /// Returns the [`Key`] in this position on a US QWERTY keyboard, if it's known.
+    pub fn qwerty_key(self) -> Option<Key> {
+        Some(match self.0 {
+            1 => Key::Escape,
+            2 => Key::Alpha1,
+            3 => Key::Alpha2,
+            4 => Key::Alpha3,
+            5 => Key::Alpha4,
+            6 => Key::Alpha5,
+            7 => Key::Alpha6,
+            8 => Key::Alpha7,
+            9 => Key::Alpha8,
+            10 => Key::Alpha9,
+            11 => Key::Alpha0,
+            12 => Key::Minus,
+            13 => Key::Equals,
+            14 => Key::Backspace,
+            15 => Key::Tab,
+            16 => Key::Q,
+            17 => Key::W,
+            18 => Key::E,
+            19 => Key::R,
+            20 => Key::T,
+            21 => Key::Y,
+            22 => Key::U,
+            23 => Key::I,
+            24 => Key::O,
+            25 => Key::P,
+            26 => Key::BracketLeft,
+            27 => Key::BracketRight,
+            28 | 96 => Key::Return, // 96 is keypad enter
+            29 => Key::LeftControl,
+            30 => Key::A,
+            31 => Key::S,
+            32 => Key::D,
+            33 => Key::F,
+            34 => Key::G,
+            35 => Key::H,
+            36 => Key::J,
+            37 => Key::K,
+            38 => Key::L,
+            39 => Key::Semicolon,
+            40 => Key::Apostrophe,
+            41 => Key::Grave,
+            42 => Key::LeftShift,
+            43 => Key::Backslash,
+            44 => Key::Z,
+            45 => Key::X,
+            46 => Key::C,
+            47 => Key::V,
+            48 => Key::B,
+            49 => Key::N,
+            50 => Key::M,
+            51 => Key::Comma,
+            52 => Key::Period,
+            53 => Key::Slash,
+            54 => Key::RightShift,
+            55 => Key::KeypadMultiply,
+            56 => Key::LeftAlt,
+            57 => Key::Space,
+            58 => Key::CapsLock,
+            59 => Key::F1,
+            60 => Key::F2,
+            61 => Key::F3,
+            62 => Key::F4,
+            63 => Key::F5,
+            64 => Key::F6,
+            65 => Key::F7,
+            66 => Key::F8,
+            67 => Key::F9,
+            68 => Key::F10,
+            69 => Key::NumLock,
+            70 => Key::ScrollLock,
+            71 => Key::Keypad7,
+            72 => Key::Keypad8,
+            73 => Key::Keypad9,
+            74 => Key::KeypadSubtract,
+            75 => Key::Keypad4,
+            76 => Key::Keypad5,
+            77 => Key::Keypad6,
+            78 => Key::KeypadAdd,
+            79 => Key::Keypad1,
+            80 => Key::Keypad2,
+            81 => Key::Keypad3,
+            82 => Key::Keypad0,
+            83 => Key::KeypadDecimal,
+            86 => Key::Oem102,
+            87 => Key::F11,
+            88 => Key::F12,
+            97 => Key::RightControl,
+            98 => Key::KeypadDivide,
+            99 => Key::PrintScreen,
+            100 => Key::RightAlt,
+            102 => Key::Home,
+            103 => Key::UpArrow,
+            104 => Key::PageUp,
+            105 => Key::LeftArrow,
+            106 => Key::RightArrow,
+            107 => Key::End,
+            108 => Key::DownArrow,
+            109 => Key::PageDown,
+            110 => Key::Insert,
+            111 => Key::Delete,
+            113 => Key::MediaVolumeMute,
+            114 => Key::MediaVolumeDown,
+            115 => Key::MediaVolumeUp,
+            119 => Key::Pause,
+            121 => Key::KeypadSeparator,
+            125 => Key::LeftSuper,
+            126 => Key::RightSuper,
+            127 => Key::Applications,
+            142 => Key::Sleep,
+            163 => Key::MediaNextTrack,
+            164 => Key::MediaPlayPause,
+            165 => Key::MediaPreviousTrack,
+            166 => Key::MediaStop,
+            183 => Key::F13,
+            184 => Key::F14,
+            185 => Key::F15,
+            186 => Key::F16,
+            187 => Key::F17,
+            188 => Key::F18,
+            189 => Key::F19,
+            190 => Key::F20,
+            191 => Key::F21,
+            192 => Key::F22,
+            193 => Key::F23,
+            194 => Key::F24,
+            _ => return None,
+        })
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
@@ -120,4 +411,321 @@ pub enum MouseButton {
     Left,
     Middle,
     Right,
+
+    /// The "back" thumb button, usually used for going back in history. Also known as X1.
+    Back,
+
+    /// The "forward" thumb button, usually used for going forward in history. Also known as X2.
+    Forward,
+
+    /// Any other button, identified by its platform-specific button number.
+    Other(u8),
+}
+
+/// Formats the button as its variant name, for example `Left` or `Other(12)`. These names are stable, and can be
+/// parsed back with [`str::parse`].
+impl std::fmt::Display for MouseButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MouseButton::Left => f.write_str("Left"),
+            MouseButton::Middle => f.write_str("Middle"),
+            MouseButton::Right => f.write_str("Right"),
+            MouseButton::Back => f.write_str("Back"),
+            MouseButton::Forward => f.write_str("Forward"),
+            MouseButton::Other(button) => write!(f, "Other({})", button),
+        }
+    }
+}
+
+/// Parses a button from its name as formatted by [`Display`](std::fmt::Display), or from a common alias such as
+/// `LMB` or `X1`. Names are not case-sensitive.
+impl std::str::FromStr for MouseButton {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        const NAMES: [(&str, MouseButton); 11] = [
+            ("Left", MouseButton::Left),
+            ("Middle", MouseButton::Middle),
+            ("Right", MouseButton::Right),
+            ("Back", MouseButton::Back),
+            ("Forward", MouseButton::Forward),
+            ("LMB", MouseButton::Left),
+            ("MMB", MouseButton::Middle),
+            ("RMB", MouseButton::Right),
+            ("X1", MouseButton::Back),
+            ("X2", MouseButton::Forward),
+            ("Wheel", MouseButton::Middle),
+        ];
+        let s = s.trim();
+        match NAMES.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)) {
+            Some(&(_, button)) => Ok(button),
+            None => parse_code(s, "Other").map(MouseButton::Other).ok_or(Error::Invalid),
+        }
+    }
+}
+
+/// The unit that the deltas in an [`Event::Scroll`] are measured in.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ScrollUnit {
+    /// Lines, or "notches" of a traditional mouse wheel. Devices which scroll smoothly, such as touchpads, will
+    /// report fractions of a line.
+    Lines,
+}
+
+/// The stage of a touch, or of a touchpad gesture, that an event is reporting.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TouchPhase {
+    /// This is the first event for it.
+    Started,
+
+    /// It's still ongoing, and has moved or changed.
+    Moved,
+
+    /// It finished normally, and this is the last event for it.
+    Ended,
+
+    /// It was taken over by the system, or interrupted some other way, and this is the last event for it.
+    /// Any effects it had should usually be undone.
+    Cancelled,
+}
+
+/// Which end of a stylus is being used with a drawing tablet.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum PenTool {
+    /// The tip of the pen.
+    Pen,
+
+    /// The eraser on the back of the pen.
+    Eraser,
+}
+
+/// A set of keyboard modifiers, such as Shift or Ctrl. These can be combined with `|`, for example
+/// `Modifiers::CTRL | Modifiers::SHIFT`.
+#[derive(Copy, Clone, Default, Eq, Hash, PartialEq)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const SHIFT: Self = Self(1 << 0);
+    pub const CTRL: Self = Self(1 << 1);
+    pub const ALT: Self = Self(1 << 2);
+    /// The "Windows" or "Command" key, depending on the keyboard.
+    pub const SUPER: Self = Self(1 << 3);
+    pub const CAPS_LOCK: Self = Self(1 << 4);
+    pub const NUM_LOCK: Self = Self(1 << 5);
+
+    const NAMES: [(Self, &'static str); 6] = [
+        (Self::SHIFT, "SHIFT"),
+        (Self::CTRL, "CTRL"),
+        (Self::ALT, "ALT"),
+        (Self::SUPER, "SUPER"),
+        (Self::CAPS_LOCK, "CAPS_LOCK"),
+        (Self::NUM_LOCK, "NUM_LOCK"),
+    ];
+
+    /// Returns a set with no modifiers in it.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Returns a set with every modifier in it.
+    pub const fn all() -> Self {
+        Self(0b111111)
+    }
+
+    /// Returns the raw bits of this set. These are not guaranteed to be stable between versions.
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Returns `true` if no modifiers are in this set.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if all the modifiers in `other` are also in this set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if any of the modifiers in `other` are also in this set.
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Adds the modifiers in `other` to this set.
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    /// Removes the modifiers in `other` from this set.
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    /// Adds or removes the modifiers in `other` depending on `value`.
+    pub fn set(&mut self, other: Self, value: bool) {
+        if value {
+            self.insert(other)
+        } else {
+            self.remove(other)
+        }
+    }
+}
+
+impl std::fmt::Debug for Modifiers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Modifiers(")?;
+        let mut first = true;
+        for (modifier, name) in Self::NAMES.iter() {
+            if self.contains(*modifier) {
+                if !first {
+                    f.write_str(" | ")?;
+                }
+                f.write_str(name)?;
+                first = false;
+            }
+        }
+        f.write_str(")")
+    }
+}
+
+impl std::ops::BitOr for Modifiers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl std::ops::BitAnd for Modifiers {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl std::ops::BitAndAssign for Modifiers {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl std::ops::Sub for Modifiers {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 & !rhs.0)
+    }
+}
+
+impl std::ops::Not for Modifiers {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0 & Self::all().0)
+    }
+}
+
+/// The keyboard layouts the user has configured, as returned by
+/// [`Connection::keyboard_layouts`](crate::connection::Connection::keyboard_layouts).
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct KeyboardLayouts {
+    /// The human-readable name of each layout, such as "English (US)".
+    pub names: Vec<String>,
+
+    /// The index into `names` of the layout which is currently active.
+    pub active: usize,
+}
+
+/// A keyboard shortcut made up of a [`Key`] and the [`Modifiers`] held with it, such as Ctrl+Shift+S.
+/// 
+/// Accelerators can be parsed from and formatted as strings like `"Ctrl+Shift+S"`, which makes them convenient for
+/// storing user-configurable hotkeys. Modifiers are written as `Ctrl`, `Alt`, `Shift` and `Super` (with `Control`,
+/// `Option`, `Win`, `Meta` and `Cmd` accepted as aliases), followed by the key's name as parsed by [`Key`].
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Accelerator {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl Accelerator {
+    const MODIFIER_NAMES: [(&'static str, Modifiers); 12] = [
+        ("Ctrl", Modifiers::CTRL),
+        ("Alt", Modifiers::ALT),
+        ("Shift", Modifiers::SHIFT),
+        ("Super", Modifiers::SUPER),
+        ("CapsLock", Modifiers::CAPS_LOCK),
+        ("NumLock", Modifiers::NUM_LOCK),
+        ("Control", Modifiers::CTRL),
+        ("Option", Modifiers::ALT),
+        ("Win", Modifiers::SUPER),
+        ("Meta", Modifiers::SUPER),
+        ("Cmd", Modifiers::SUPER),
+        ("Command", Modifiers::SUPER),
+    ];
+
+    /// Creates an accelerator from a key and the modifiers which must be held with it.
+    pub const fn new(modifiers: Modifiers, key: Key) -> Self {
+        Self { modifiers, key }
+    }
+
+    /// Returns `true` if the event is a press of this accelerator: a [`KeyboardDown`](Event::KeyboardDown) or
+    /// [`KeyboardRepeat`](Event::KeyboardRepeat) of the same key, with exactly the same modifiers held.
+    /// 
+    /// Caps Lock and Num Lock are ignored unless they're part of the accelerator, since users don't expect those
+    /// to stop shortcuts from working.
+    pub fn matches(&self, event: &Event) -> bool {
+        match event {
+            Event::KeyboardDown { key, modifiers, .. } | Event::KeyboardRepeat { key, modifiers, .. } => {
+                let locks = (Modifiers::CAPS_LOCK | Modifiers::NUM_LOCK) - self.modifiers;
+                *key == self.key && *modifiers - locks == self.modifiers
+            },
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Accelerator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, modifier) in Self::MODIFIER_NAMES[..6].iter() {
+            if self.modifiers.contains(*modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl std::str::FromStr for Accelerator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        // The key comes after the last `+`, unless the key is `+` itself, as in "Ctrl++"
+        let s = s.trim();
+        let (modifiers, key) = match s.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.trim_end().ends_with('+') => (rest.trim_end(), "+"),
+            _ => match s.rfind('+') {
+                Some(i) => (&s[..i + 1], &s[i + 1..]),
+                None => ("", s),
+            },
+        };
+
+        let mut parsed = Modifiers::empty();
+        for name in modifiers.split_terminator('+') {
+            let name = name.trim();
+            match Self::MODIFIER_NAMES.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+                Some((_, modifier)) => parsed.insert(*modifier),
+                None => return Err(Error::Invalid),
+            }
+        }
+        Ok(Self::new(parsed, key.parse()?))
+    }
 }
@@ -5,9 +5,14 @@
 #[macro_use]
 pub(crate) mod util;
 
+pub mod clipboard;
 pub mod connection;
+pub mod dnd;
 pub mod error;
 pub mod event;
+#[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "gamepad")))]
+#[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "gamepad"))]
+pub mod gamepad;
 #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
 #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
 pub mod input;
@@ -27,4 +32,183 @@ mod tests {
         test::<crate::window::Style>();
         test::<crate::window::Window>();
     }
+
+    #[cfg(feature = "input")]
+    #[test]
+    fn accelerator_round_trip() {
+        use crate::input::{Accelerator, Key, Modifiers};
+
+        let accel: Accelerator = "ctrl + shift+s".parse().unwrap();
+        assert_eq!(accel, Accelerator::new(Modifiers::CTRL | Modifiers::SHIFT, Key::S));
+        assert_eq!(accel.to_string(), "Ctrl+Shift+S");
+        assert_eq!("Ctrl++".parse::<Accelerator>().unwrap(), Accelerator::new(Modifiers::CTRL, Key::Plus));
+        assert_eq!("PgUp".parse::<Key>().unwrap(), Key::PageUp);
+        assert_eq!(Key::Unknown(0x1008ff13).to_string().parse::<Key>().unwrap(), Key::Unknown(0x1008ff13));
+        assert!("Ctrl+".parse::<Accelerator>().is_err());
+    }
+
+    #[cfg(all(target_os = "linux", feature = "input"))]
+    #[test]
+    fn touch_recorded_events() {
+        use crate::{event::Event, input::TouchPhase, platform::linux::Touches};
+
+        // Type (TouchBegin, TouchUpdate and TouchEnd are 18, 19 and 20), touch ID and position of each event
+        let recorded = [
+            (18, 5, (10.0, 20.0)),
+            (18, 6, (50.5, 60.0)),
+            (19, 5, (12.0, 21.5)),
+            (20, 6, (51.0, 60.0)),
+            (19, 7, (0.0, 0.0)),
+            (19, 5, (14.0, 23.0)),
+        ];
+        let mut touches = Touches::default();
+        let mut events = recorded
+            .iter()
+            .filter_map(|&(event_type, id, position)| touches.event(event_type, id, position))
+            .collect::<Vec<_>>();
+        // The window being unmapped
+        touches.cancel(&mut events);
+
+        let touch = |id, phase, position| Event::Touch { id, phase, position };
+        assert_eq!(events, [
+            touch(5, TouchPhase::Started, (10.0, 20.0)),
+            touch(6, TouchPhase::Started, (50.5, 60.0)),
+            touch(5, TouchPhase::Moved, (12.0, 21.5)),
+            touch(6, TouchPhase::Ended, (51.0, 60.0)),
+            touch(5, TouchPhase::Moved, (14.0, 23.0)),
+            touch(5, TouchPhase::Cancelled, (14.0, 23.0)),
+        ]);
+        touches.cancel(&mut events);
+        assert_eq!(events.len(), 6);
+    }
+
+    // This needs an X server, for example `xvfb-run cargo test -- --ignored`
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore]
+    fn clipboard_between_connections() {
+        use crate::connection::Connection;
+        use std::{sync::mpsc, thread};
+
+        // Big enough to need an INCR transfer
+        let data = (0..1 << 21).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let owner = Connection::new().unwrap();
+        let mut window = owner.builder().visible(false).build().unwrap();
+        owner.clipboard().set(&[("application/x-ramen-test", &data), ("text/plain;charset=utf-8", b"ramen")]).unwrap();
+
+        // The owner only answers requests while its events are being read, so the other side needs its own thread
+        let (sender, receiver) = mpsc::channel();
+        let _ = thread::spawn(move || {
+            let clipboard = Connection::new().unwrap().clipboard();
+            let result = (clipboard.targets(), clipboard.get("application/x-ramen-test"), clipboard.get_text());
+            sender.send(result).unwrap();
+        });
+        let (targets, received, text) = loop {
+            window.poll_events();
+            if let Ok(result) = receiver.try_recv() {
+                break result
+            }
+            thread::sleep(std::time::Duration::from_millis(1));
+        };
+        assert!(targets.iter().any(|t| t == "application/x-ramen-test"));
+        assert!(targets.iter().any(|t| t == "UTF8_STRING"));
+        assert_eq!(received.as_deref(), Some(&data[..]));
+        assert_eq!(text.as_deref(), Some("ramen"));
+    }
+
+    // Dragging between two windows of the same connection, with enough data to need an INCR transfer. This needs an X
+    // server without a window manager, so that the windows go where they're put, for example `xvfb-run cargo test --
+    // --ignored`
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore]
+    fn drag_between_own_windows() {
+        use crate::{
+            connection::Connection,
+            dnd::{DragAction, DragData, DropData},
+            event::Event,
+            platform::linux::send_button_release,
+            window::Window,
+        };
+        use std::{thread, time::Duration};
+
+        // Polls both windows until `f` has returned true for `count` of their events, or a few seconds have passed
+        fn wait_for(mut windows: [&mut Window; 2], count: usize, mut f: impl FnMut(&Event) -> bool) -> bool {
+            let mut found = 0;
+            for _ in 0..5000 {
+                for window in windows.iter_mut() {
+                    window.poll_events();
+                    found += window.events().into_iter().filter(|event| f(event)).count();
+                }
+                if found >= count {
+                    return true
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+            false
+        }
+
+        let data = (0..1 << 21).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let connection = Connection::new().unwrap();
+        let mut source = connection.builder().position(Some((0, 0))).size((100, 100)).build().unwrap();
+        let builder = connection.builder().position(Some((200, 0))).size((100, 100)).accept_drops(true);
+        let mut target = builder.build().unwrap();
+        assert!(wait_for([&mut source, &mut target], 2, |event| *event == Event::Visible(true)));
+
+        source.set_cursor_position((50, 50));
+        let drag = DragData::Data(vec![("application/x-ramen-test".to_owned(), data.clone())]);
+        source.start_drag(drag, &[DragAction::Move]).unwrap();
+        target.set_cursor_position((50, 50));
+        assert!(wait_for([&mut source, &mut target], 1, |event| matches!(event, Event::DragEnter { .. })));
+
+        send_button_release(&connection, source.xid());
+        let (mut dropped, mut ended) = (None, None);
+        assert!(wait_for([&mut source, &mut target], 2, |event| match event {
+            Event::Drop { data: DropData::Data { data, .. }, .. } => dropped.replace(data.clone()).is_none(),
+            Event::DragEnd { action } => ended.replace(*action).is_none(),
+            _ => false,
+        }));
+        assert_eq!(dropped.as_deref(), Some(&data[..]));
+        assert_eq!(ended, Some(Some(DragAction::Move)));
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn gamepad_recorded_stream() {
+        use crate::gamepad::{Axis, AxisInfo, Button, DeviceInfo, DeviceSource, GamepadEvent, Gamepads, RawEvent};
+
+        struct Recorded(Vec<RawEvent>);
+        impl DeviceSource for Recorded {
+            fn poll(&mut self, events: &mut Vec<RawEvent>) {
+                events.append(&mut self.0);
+            }
+        }
+
+        let axis = |code, min, max| AxisInfo { code, min, max };
+        let info = DeviceInfo {
+            name: "Raw Pad".into(),
+            guid: "030000005e0400008e02000010010000".parse().unwrap(),
+            buttons: vec![0x130, 0x131, 0x133, 0x134],
+            axes: vec![axis(0x00, -32768, 32767), axis(0x02, 0, 255), axis(0x12, -1, 1), axis(0x13, -1, 1)],
+            rumble: false,
+        };
+        let input = |kind, code, value| RawEvent::Input { device: 7, kind, code, value };
+        let mut pads = Gamepads::with_source(Recorded(vec![
+            RawEvent::Added { device: 7, info },
+            input(0x01, 0x131, 1),
+            input(0x03, 0x02, 255),
+            input(0x03, 0x13, -1),
+            RawEvent::Removed { device: 8 },
+        ]));
+        let db = "# comment\n030000005e0400008e02000000000000,Mapped Pad,a:b1,b:b0,lefttrigger:a1,dpup:h0.1,\n";
+        assert_eq!(pads.add_mappings(db), 1);
+
+        pads.poll_events();
+        let id = pads.connected().next().unwrap();
+        assert_eq!(pads.name(id), Some("Mapped Pad"));
+        assert_eq!(pads.events().into_iter().next(), Some(&GamepadEvent::Connected(id)));
+        assert!(pads.button(id, Button::A) && !pads.button(id, Button::B));
+        assert!(pads.button(id, Button::DPadUp));
+        assert_eq!(pads.axis(id, Axis::LeftTrigger), 1.0);
+    }
 }
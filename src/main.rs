@@ -1,7 +1,7 @@
-use ramen::{connection::Connection, event::Event, window::Cursor}; // There's no actual error here it's an RA bug sorry in advance
+use ramen::{connection::Connection, event::Event, window::{Cursor, CursorGrab}}; // There's no actual error here it's an RA bug sorry in advance
 
 #[cfg(feature = "input")]
-use ramen::input::Key;
+use ramen::{input::Key, window::CursorMode};
 
 pub fn main() {
     let c = match Connection::new() {
@@ -27,6 +27,9 @@ pub fn main() {
 pub fn f(connection: Connection) {
     let mut borderless = false;
     let mut resizable = false;
+    let mut confined = false;
+    #[cfg(feature = "input")]
+    let mut locked = false;
     let mut window = match connection
         .into_builder()
         .controls(Some(ramen::window::Controls::new()))
@@ -73,9 +76,9 @@ pub fn f(connection: Connection) {
                     println!("Window{} visible", if *t { "" } else { " not" });
                 },
                 #[cfg(feature = "input")]
-                Event::KeyboardDown(k) => {
-                    println!("Key down: {:?}", k);
-                    match k {
+                Event::KeyboardDown { key, scancode, modifiers, .. } => {
+                    println!("Key down: {:?} {:?} {:?}", key, scancode, modifiers);
+                    match key {
                         Key::T => window.set_title("This is a different title"),
                         Key::M => window.set_maximised(true),
                         Key::N => window.set_maximised(false),
@@ -92,30 +95,50 @@ pub fn f(connection: Connection) {
                         Key::C => {
                             window.set_cursor(Cursor::Cross);
                         },
+                        Key::G => {
+                            confined = !confined;
+                            window.set_cursor_grab(if confined { CursorGrab::Confined } else { CursorGrab::None });
+                        },
+                        Key::L => {
+                            locked = !locked;
+                            window.set_cursor_mode(if locked { CursorMode::Locked } else { CursorMode::Normal });
+                        },
                         _ => (),
                     }
                 },
                 #[cfg(feature = "input")]
-                Event::KeyboardRepeat(k) => {
-                    println!("Key repeat: {:?}", k);
+                Event::KeyboardRepeat { key, .. } => {
+                    println!("Key repeat: {:?}", key);
+                },
+                #[cfg(feature = "input")]
+                Event::KeyboardUp { key, .. } => {
+                    println!("Key up: {:?}", key);
                 },
                 #[cfg(feature = "input")]
-                Event::KeyboardUp(k) => {
-                    println!("Key up: {:?}", k);
+                Event::ModifiersChanged(modifiers) => {
+                    println!("Modifiers changed: {:?}", modifiers);
                 },
                 #[cfg(feature = "input")]
-                Event::MouseDown(k) => {
-                    println!("Mouse down: {:?}", k);
+                Event::MouseDown { button, modifiers } => {
+                    println!("Mouse down: {:?} {:?}", button, modifiers);
                 },
                 #[cfg(feature = "input")]
-                Event::MouseUp(k) => {
-                    println!("Mouse up: {:?}", k);
+                Event::MouseUp { button, .. } => {
+                    println!("Mouse up: {:?}", button);
                 },
                 #[cfg(feature = "input")]
                 Event::MouseMove(t) => {
                     println!("Mouse move: {:?}", t);
                 },
                 #[cfg(feature = "input")]
+                Event::RawMouseMotion { dx, dy } => {
+                    println!("Raw mouse motion: ({}, {})", dx, dy);
+                },
+                #[cfg(feature = "input")]
+                Event::Scroll { dx, dy, unit } => {
+                    println!("Scroll: ({}, {}) {:?}", dx, dy, unit);
+                },
+                #[cfg(feature = "input")]
                 Event::ScrollUp => {
                     println!("Mouse scrolled up");
                 },
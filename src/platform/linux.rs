@@ -1,7 +1,18 @@
+mod clipboard;
+mod dnd;
 mod ffi;
+#[cfg(feature = "input")]
+mod ime;
 mod imp;
+#[cfg(feature = "input")]
+mod keyboard;
+mod x11;
 
 pub use self::ffi::xcb_window_t;
 pub use self::ffi::Display;
 
 pub(crate) use imp::{Connection, Window};
+#[cfg(test)]
+pub(crate) use imp::send_button_release;
+#[cfg(all(test, feature = "input"))]
+pub(crate) use imp::Touches;
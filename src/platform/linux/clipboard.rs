@@ -0,0 +1,721 @@
+// Clipboard handling through X11 selections (ICCCM section 2)
+//
+// Selections are owned by windows, so we create an invisible window which owns ours and which other clients' data
+// gets sent to. Other clients ask for our data by sending us a SelectionRequest, and we answer by writing it to a
+// property on their window then sending them a SelectionNotify. We ask for theirs the same way, the other way round.
+//
+// Events for our window are picked out of the event stream wherever it's being read, see `process_event`. When we
+// need an answer from another client, we block until it arrives and read events as we go, so that we can still
+// answer requests (including our own) and so that events for other windows get queued up as usual.
+//
+// Data that's too big to send in one request is sent in chunks with the INCR protocol (ICCCM section 2.7.2): the owner
+// writes a property of type INCR, then writes each chunk once the requestor has deleted the last one, finishing with an
+// empty chunk.
+//
+// Changes of ownership are reported by the XFixes extension, if it's available. Like keyboard layout changes, these
+// aren't specific to any window, so we keep a serial for each selection which windows check when they poll.
+//
+// The data being dragged out of our windows is served the same way, through the XdndSelection selection, see
+// `set_drag_data` and `dnd::DragSource`.
+//
+// Since our data goes away with us, we hand the clipboard over to the clipboard manager when the connection closes,
+// if one is running (freedesktop.org's ClipboardManager specification). It takes a copy by asking us for each target.
+
+use super::{ffi::*, x11::{atom_names, intern_atoms, read_property}};
+use crate::{clipboard::Selection, error::Error};
+
+use std::{ptr, sync::Arc, time::{Duration, Instant}};
+
+// How long to wait for another client to answer before giving up on it
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+// How long to give the clipboard manager to copy everything, which can take several requests
+const SAVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The most we'll send in one INCR chunk, even if the server would allow more
+const MAX_CHUNK_SIZE: usize = 1 << 18;
+
+// The index of XdndSelection in `Clipboard::owned`, after the ones for each `Selection`
+const DRAG: usize = 2;
+
+// Targets which are part of the selection protocol rather than formats the data is available in
+const META_TARGETS: [&str; 5] = ["TARGETS", "TIMESTAMP", "MULTIPLE", "SAVE_TARGETS", "DELETE"];
+
+pub(super) struct Clipboard {
+    connection: *mut xcb_connection_t,
+    window: xcb_window_t,
+    atoms: ClipboardAtoms,
+    // What we're offering for each selection, indexed by `Self::index`
+    owned: [Option<Owned>; 3],
+    transfers: Vec<Transfer>,
+    // Data bigger than this is sent with INCR
+    chunk_size: usize,
+    // The response type of XFixes' SelectionNotify, or `None` if XFixes isn't available
+    xfixes_event: Option<u8>,
+    // Incremented whenever each selection's owner changes, indexed by `Self::index`
+    serials: [u32; 2],
+    // The base and mask of the IDs this connection gives the windows it creates, see `is_ours`
+    resource_ids: (u32, u32),
+}
+
+#[derive(Clone, Copy)]
+struct ClipboardAtoms {
+    clipboard: xcb_atom_t,
+    targets: xcb_atom_t,
+    timestamp: xcb_atom_t,
+    utf8_string: xcb_atom_t,
+    text_plain: xcb_atom_t,
+    incr: xcb_atom_t,
+    xdnd_selection: xcb_atom_t,
+    clipboard_manager: xcb_atom_t,
+    save_targets: xcb_atom_t,
+    // The property on our window that other clients write their data to
+    transfer: xcb_atom_t,
+}
+
+struct Owned {
+    // When we took ownership, which we have to report to anyone who asks for TIMESTAMP
+    time: xcb_timestamp_t,
+    // Each target we can convert the selection to, with its data
+    data: Vec<(xcb_atom_t, Arc<[u8]>)>,
+}
+
+// An INCR transfer we're sending, see `continue_transfer`
+struct Transfer {
+    requestor: xcb_window_t,
+    property: xcb_atom_t,
+    target: xcb_atom_t,
+    data: Arc<[u8]>,
+    // How much of `data` has been sent so far
+    offset: usize,
+}
+
+unsafe impl Send for Clipboard {}
+
+impl Clipboard {
+    pub(super) unsafe fn new(connection: *mut xcb_connection_t, root: xcb_window_t) -> Result<Self, Error> {
+        let names = [
+            "CLIPBOARD",
+            "TARGETS",
+            "TIMESTAMP",
+            "UTF8_STRING",
+            "text/plain;charset=utf-8",
+            "INCR",
+            "XdndSelection",
+            "CLIPBOARD_MANAGER",
+            "SAVE_TARGETS",
+            "RAMEN_SELECTION",
+        ];
+        let mut atoms = [0 as xcb_atom_t; 10];
+        intern_atoms(connection, &names, &mut atoms)?;
+
+        let window = xcb_generate_id(connection);
+        if window == !0u32 {
+            return Err(Error::SystemResources)
+        }
+        // We only need PropertyNotify for getting timestamps, selection events are always sent regardless of mask
+        let event_mask = XCB_EVENT_MASK_PROPERTY_CHANGE;
+        let error = xcb_request_check(connection, xcb_create_window_checked(
+            connection,
+            0,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            XCB_WINDOW_CLASS_INPUT_ONLY,
+            XCB_COPY_FROM_PARENT.into(),
+            XCB_CW_EVENT_MASK,
+            &event_mask,
+        ));
+        if !error.is_null() {
+            free(error.cast());
+            return Err(Error::SystemResources)
+        }
+
+        Ok(Self {
+            connection,
+            window,
+            atoms: ClipboardAtoms {
+                clipboard: atoms[0],
+                targets: atoms[1],
+                timestamp: atoms[2],
+                utf8_string: atoms[3],
+                text_plain: atoms[4],
+                incr: atoms[5],
+                xdnd_selection: atoms[6],
+                clipboard_manager: atoms[7],
+                save_targets: atoms[8],
+                transfer: atoms[9],
+            },
+            owned: [None, None, None],
+            transfers: Vec::new(),
+            // ChangeProperty's header takes up 24 bytes of the request
+            chunk_size: (xcb_get_maximum_request_length(connection) as usize * 4 - 24).min(MAX_CHUNK_SIZE),
+            xfixes_event: select_owner_changes(connection, window, &[atoms[0], XCB_ATOM_PRIMARY]),
+            serials: [0, 0],
+            resource_ids: {
+                let setup = &*xcb_get_setup(connection);
+                (setup.resource_id_base, setup.resource_id_mask)
+            },
+        })
+    }
+
+    // Handles the event if it's destined for our window, returning whether it was. Handled events are freed.
+    // We also watch for property changes on the windows of clients we're sending INCR transfers to.
+    pub(super) unsafe fn process_event(&mut self, ev: *mut xcb_generic_event_t) -> bool {
+        let window = match self.event_window(ev) {
+            Some(window) => window,
+            None => return false,
+        };
+        // The only events we want for other windows are requestors deleting the chunks we've sent them. The requestor
+        // might be one of our own windows receiving a drop, which needs everything else for itself.
+        if window != self.window {
+            if !self.chunk_deleted(ev, window) {
+                return false
+            }
+            free(ev.cast());
+            return true
+        }
+        match (*ev).response_type & !(1 << 7) {
+            XCB_SELECTION_REQUEST => self.serve(&*(ev as *const xcb_selection_request_event_t)),
+            XCB_SELECTION_CLEAR => {
+                let event = &*(ev as *const xcb_selection_clear_event_t);
+                if let Some(i) = self.index(event.selection) {
+                    self.owned[i] = None;
+                }
+            },
+            t if Some(t) == self.xfixes_event => {
+                let event = &*(ev as *const xcb_xfixes_selection_notify_event_t);
+                if let Some(serial) = self.index(event.selection).and_then(|i| self.serials.get_mut(i)) {
+                    *serial = serial.wrapping_add(1);
+                }
+            },
+            XCB_PROPERTY_NOTIFY => {
+                let _ = self.chunk_deleted(ev, window);
+            },
+            _ => (),
+        }
+        free(ev.cast());
+        true
+    }
+
+    // Sends the next chunk of an INCR transfer if the event is its requestor deleting the last one, returning whether
+    // it was
+    unsafe fn chunk_deleted(&mut self, ev: *mut xcb_generic_event_t, window: xcb_window_t) -> bool {
+        if (*ev).response_type & !(1 << 7) != XCB_PROPERTY_NOTIFY {
+            return false
+        }
+        let event = &*(ev as *const xcb_property_notify_event_t);
+        if event.state != XCB_PROPERTY_DELETE {
+            return false
+        }
+        let transfer = |t: &Transfer| t.requestor == window && t.property == event.atom;
+        match self.transfers.iter().position(transfer) {
+            Some(i) => {
+                self.continue_transfer(i);
+                true
+            },
+            None => false,
+        }
+    }
+
+    // Whether a window was created by this connection, which means it's one of our own windows that already selects
+    // PropertyNotify events for itself, and that we mustn't change the event mask of
+    fn is_ours(&self, window: xcb_window_t) -> bool {
+        let (base, mask) = self.resource_ids;
+        window & !mask == base
+    }
+
+    pub(super) fn serial(&self, selection: Selection) -> u32 {
+        self.serials[selection as usize]
+    }
+
+    // Returns the names of the targets the selection can be converted to
+    pub(super) unsafe fn targets(
+        &mut self,
+        selection: Selection,
+        route: &mut dyn FnMut(*mut xcb_generic_event_t),
+    ) -> Vec<String> {
+        let atoms = match &self.owned[selection as usize] {
+            Some(owned) => owned.data.iter().map(|(target, _)| *target).collect(),
+            None => match self.get(selection, self.atoms.targets, route) {
+                Some(data) => data.chunks_exact(4).map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]])).collect(),
+                None => Vec::new(),
+            },
+        };
+        let mut names = atom_names(self.connection, &atoms);
+        names.retain(|name| !META_TARGETS.contains(&name.as_str()));
+        names
+    }
+
+    pub(super) unsafe fn get_data(
+        &mut self,
+        selection: Selection,
+        mime_type: &str,
+        route: &mut dyn FnMut(*mut xcb_generic_event_t),
+    ) -> Option<Vec<u8>> {
+        let mut target = [0];
+        intern_atoms(self.connection, &[mime_type], &mut target).ok()?;
+        self.get(selection, target[0], route)
+    }
+
+    pub(super) unsafe fn set_data(
+        &mut self,
+        selection: Selection,
+        formats: &[(&str, &[u8])],
+        route: &mut dyn FnMut(*mut xcb_generic_event_t),
+    ) -> Result<(), Error> {
+        self.set_formats(selection as usize, formats, route)
+    }
+
+    // Takes ownership of XdndSelection for a drag that's starting, returning the targets the data is offered as.
+    // The data is kept until the next drag replaces it, since we can't tell when the target has finished asking for it.
+    pub(super) unsafe fn set_drag_data(
+        &mut self,
+        formats: &[(&str, &[u8])],
+        route: &mut dyn FnMut(*mut xcb_generic_event_t),
+    ) -> Result<Vec<xcb_atom_t>, Error> {
+        self.set_formats(DRAG, formats, route)?;
+        Ok(self.owned[DRAG].iter().flat_map(|owned| owned.data.iter().map(|(target, _)| *target)).collect())
+    }
+
+    unsafe fn set_formats(
+        &mut self,
+        index: usize,
+        formats: &[(&str, &[u8])],
+        route: &mut dyn FnMut(*mut xcb_generic_event_t),
+    ) -> Result<(), Error> {
+        let names = formats.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        let mut atoms = vec![0; names.len()];
+        intern_atoms(self.connection, &names, &mut atoms)?;
+        let mut data = atoms
+            .into_iter()
+            .zip(formats)
+            .map(|(atom, (_, data))| (atom, Arc::from(*data)))
+            .collect::<Vec<(xcb_atom_t, Arc<[u8]>)>>();
+
+        // Older X11 clients only know plain text by its X11-specific name
+        let text = data.iter().find(|(target, _)| *target == self.atoms.text_plain).map(|(_, text)| text.clone());
+        if let Some(text) = text {
+            if !data.iter().any(|(target, _)| *target == self.atoms.utf8_string) {
+                data.push((self.atoms.utf8_string, text));
+            }
+        }
+        self.set(index, data, route)
+    }
+
+    pub(super) unsafe fn get_text(
+        &mut self,
+        selection: Selection,
+        route: &mut dyn FnMut(*mut xcb_generic_event_t),
+    ) -> Option<String> {
+        // Fall back to Latin-1 for really old clients, which conveniently maps directly onto Unicode code points
+        match self.get(selection, self.atoms.utf8_string, route) {
+            Some(data) => String::from_utf8(data).ok(),
+            None => {
+                let data = self.get(selection, XCB_ATOM_STRING, route)?;
+                Some(data.iter().map(|&b| char::from(b)).collect())
+            },
+        }
+    }
+
+    pub(super) unsafe fn set_text(
+        &mut self,
+        selection: Selection,
+        text: &str,
+        route: &mut dyn FnMut(*mut xcb_generic_event_t),
+    ) -> Result<(), Error> {
+        let data: Arc<[u8]> = text.as_bytes().into();
+        let data = vec![(self.atoms.utf8_string, data.clone()), (self.atoms.text_plain, data)];
+        self.set(selection as usize, data, route)
+    }
+
+    // Asks the clipboard manager to take a copy of the clipboard, if we own it, and waits for it to finish
+    pub(super) unsafe fn persist(&mut self, route: &mut dyn FnMut(*mut xcb_generic_event_t)) -> Result<(), Error> {
+        let owned = match &self.owned[Selection::Clipboard as usize] {
+            Some(owned) => owned,
+            None => return Ok(()),
+        };
+        let c = self.connection;
+        let manager = self.atoms.clipboard_manager;
+        let reply = xcb_get_selection_owner_reply(c, xcb_get_selection_owner(c, manager), ptr::null_mut());
+        if reply.is_null() {
+            return Err(Error::SystemResources)
+        }
+        let owner = (*reply).owner;
+        free(reply.cast());
+        if owner == XCB_WINDOW_NONE {
+            return Err(Error::Unsupported)
+        }
+
+        // The property lists which targets the manager should save, otherwise it would ask for TARGETS and save all of
+        // them, including the ones we only offer for compatibility
+        let targets = owned.data.iter().map(|(target, _)| *target).collect::<Vec<_>>();
+        let _ = xcb_change_property(
+            c,
+            XCB_PROP_MODE_REPLACE,
+            self.window,
+            self.atoms.transfer,
+            XCB_ATOM_ATOM,
+            32,
+            targets.len() as _,
+            targets.as_ptr().cast(),
+        );
+        let (window, save_targets) = (self.window, self.atoms.save_targets);
+        let _ = xcb_convert_selection(c, window, manager, save_targets, self.atoms.transfer, XCB_CURRENT_TIME);
+        let property = self.wait_for(route, SAVE_TIMEOUT, |ev| match (*ev).response_type & !(1 << 7) {
+            XCB_SELECTION_NOTIFY => {
+                let event = &*(ev as *const xcb_selection_notify_event_t);
+                (event.requestor == window && event.selection == manager).then(|| event.property)
+            },
+            _ => None,
+        });
+        match property {
+            Some(property) if property != XCB_ATOM_NONE => Ok(()),
+            _ => Err(Error::Unknown),
+        }
+    }
+
+    // Converts the selection to the given target, returning `None` if it's empty or its owner refuses
+    unsafe fn get(
+        &mut self,
+        selection: Selection,
+        target: xcb_atom_t,
+        route: &mut dyn FnMut(*mut xcb_generic_event_t),
+    ) -> Option<Vec<u8>> {
+        // There's no need to go through the server if we'd only be asking ourselves
+        if let Some(owned) = &self.owned[selection as usize] {
+            return owned.data.iter().find(|(t, _)| *t == target).map(|(_, data)| data.to_vec())
+        }
+
+        let c = self.connection;
+        let selection = self.atom(selection);
+        let _ = xcb_convert_selection(c, self.window, selection, target, self.atoms.transfer, XCB_CURRENT_TIME);
+        let window = self.window;
+        let property = self.wait_for(route, TIMEOUT, |ev| match (*ev).response_type & !(1 << 7) {
+            XCB_SELECTION_NOTIFY => {
+                let event = &*(ev as *const xcb_selection_notify_event_t);
+                let ours = event.requestor == window && event.selection == selection && event.target == target;
+                ours.then(|| event.property)
+            },
+            _ => None,
+        })?;
+        if property == XCB_ATOM_NONE {
+            return None
+        }
+        let (r#type, mut data) = self.read_transfer()?;
+        if r#type != self.atoms.incr {
+            return Some(data)
+        }
+
+        // Deleting the INCR property tells the owner to start sending chunks, and deleting each chunk asks for the next
+        data.clear();
+        loop {
+            let transfer = self.atoms.transfer;
+            self.wait_for(route, TIMEOUT, |ev| match (*ev).response_type & !(1 << 7) {
+                XCB_PROPERTY_NOTIFY => {
+                    let event = &*(ev as *const xcb_property_notify_event_t);
+                    let ours = event.window == window && event.atom == transfer;
+                    (ours && event.state == XCB_PROPERTY_NEW_VALUE).then(|| ())
+                },
+                _ => None,
+            })?;
+            let (_, chunk) = self.read_transfer()?;
+            if chunk.is_empty() {
+                return Some(data)
+            }
+            data.extend_from_slice(&chunk);
+        }
+    }
+
+    // Takes ownership of the selection at the given index, offering the given targets
+    unsafe fn set(
+        &mut self,
+        index: usize,
+        data: Vec<(xcb_atom_t, Arc<[u8]>)>,
+        route: &mut dyn FnMut(*mut xcb_generic_event_t),
+    ) -> Result<(), Error> {
+        let c = self.connection;
+        let time = self.timestamp(route).ok_or(Error::Unknown)?;
+        let atom = self.selections()[index];
+        let _ = xcb_set_selection_owner(c, self.window, atom, time);
+
+        // Setting the owner fails silently if someone else took it more recently than `time`, so we check
+        let reply = xcb_get_selection_owner_reply(c, xcb_get_selection_owner(c, atom), ptr::null_mut());
+        if reply.is_null() {
+            return Err(Error::SystemResources)
+        }
+        let owner = (*reply).owner;
+        free(reply.cast());
+        if owner != self.window {
+            return Err(Error::Unknown)
+        }
+        self.owned[index] = Some(Owned { time, data });
+        Ok(())
+    }
+
+    // Answers a request for one of our selections
+    unsafe fn serve(&mut self, request: &xcb_selection_request_event_t) {
+        let c = self.connection;
+        // Obsolete clients may not give a property, in which case they expect the target to be used (ICCCM 2.2)
+        let property = if request.property == XCB_ATOM_NONE { request.target } else { request.property };
+        let owned = match self.index(request.selection).and_then(|i| self.owned[i].as_ref()) {
+            // Requests from before we took ownership are meant for the previous owner
+            Some(owned) if request.time == XCB_CURRENT_TIME || request.time >= owned.time => owned,
+            _ => return self.notify(request, XCB_ATOM_NONE),
+        };
+
+        if request.target == self.atoms.targets {
+            let mut targets = vec![self.atoms.targets, self.atoms.timestamp];
+            targets.extend(owned.data.iter().map(|(target, _)| *target));
+            let _ = xcb_change_property(
+                c,
+                XCB_PROP_MODE_REPLACE,
+                request.requestor,
+                property,
+                XCB_ATOM_ATOM,
+                32,
+                targets.len() as _,
+                targets.as_ptr().cast(),
+            );
+        } else if request.target == self.atoms.timestamp {
+            let _ = xcb_change_property(
+                c,
+                XCB_PROP_MODE_REPLACE,
+                request.requestor,
+                property,
+                XCB_ATOM_INTEGER,
+                32,
+                1,
+                (&owned.time as *const xcb_timestamp_t).cast(),
+            );
+        } else if let Some((target, data)) = owned.data.iter().find(|(target, _)| *target == request.target) {
+            if data.len() > self.chunk_size {
+                // We need to know when the requestor deletes each chunk, so we can send the next one
+                let data = data.clone();
+                if !self.is_ours(request.requestor) {
+                    let event_mask = XCB_EVENT_MASK_PROPERTY_CHANGE;
+                    let event_mask = (&event_mask as *const u32).cast();
+                    let _ = xcb_change_window_attributes(c, request.requestor, XCB_CW_EVENT_MASK, event_mask);
+                }
+                // The INCR property holds a lower bound on the size of the data
+                let size = data.len().min(u32::MAX as usize) as u32;
+                let _ = xcb_change_property(
+                    c,
+                    XCB_PROP_MODE_REPLACE,
+                    request.requestor,
+                    property,
+                    self.atoms.incr,
+                    32,
+                    1,
+                    (&size as *const u32).cast(),
+                );
+                let (requestor, target) = (request.requestor, request.target);
+                self.transfers.retain(|t| t.requestor != requestor || t.property != property);
+                self.transfers.push(Transfer { requestor, property, target, data, offset: 0 });
+            } else {
+                let _ = xcb_change_property(
+                    c,
+                    XCB_PROP_MODE_REPLACE,
+                    request.requestor,
+                    property,
+                    *target,
+                    8,
+                    data.len() as _,
+                    data.as_ptr().cast(),
+                );
+            }
+        } else {
+            return self.notify(request, XCB_ATOM_NONE)
+        }
+        self.notify(request, property);
+    }
+
+    // Sends the next chunk of an INCR transfer, after the requestor has deleted the last one
+    unsafe fn continue_transfer(&mut self, i: usize) {
+        let transfer = &mut self.transfers[i];
+        let chunk = &transfer.data[transfer.offset..(transfer.offset + self.chunk_size).min(transfer.data.len())];
+        let _ = xcb_change_property(
+            self.connection,
+            XCB_PROP_MODE_REPLACE,
+            transfer.requestor,
+            transfer.property,
+            transfer.target,
+            8,
+            chunk.len() as _,
+            chunk.as_ptr().cast(),
+        );
+        transfer.offset += chunk.len();
+
+        // The empty chunk at the end tells the requestor we're done, at which point we can stop watching their window
+        if chunk.is_empty() {
+            let requestor = transfer.requestor;
+            let _ = self.transfers.remove(i);
+            if !self.is_ours(requestor) && !self.transfers.iter().any(|t| t.requestor == requestor) {
+                let event_mask = 0u32;
+                let _ = xcb_change_window_attributes(
+                    self.connection,
+                    requestor,
+                    XCB_CW_EVENT_MASK,
+                    (&event_mask as *const u32).cast(),
+                );
+            }
+        }
+        let _ = xcb_flush(self.connection);
+    }
+
+    // Tells a requestor that we've answered their request, with `XCB_ATOM_NONE` as the property if we've refused it
+    unsafe fn notify(&self, request: &xcb_selection_request_event_t, property: xcb_atom_t) {
+        // Events are always sent as 32 bytes, even if the event structure is smaller
+        let mut event = [0u8; 32];
+        ptr::write(event.as_mut_ptr().cast(), xcb_selection_notify_event_t {
+            response_type: XCB_SELECTION_NOTIFY,
+            _pad0: 0,
+            sequence: 0,
+            time: request.time,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            property,
+        });
+        xcb_discard_reply(self.connection, xcb_send_event_checked(
+            self.connection,
+            false.into(),
+            request.requestor,
+            0,
+            event.as_ptr().cast(),
+        ));
+        let _ = xcb_flush(self.connection);
+    }
+
+    // Reads and deletes the transfer property on our window, returning its type and data
+    unsafe fn read_transfer(&self) -> Option<(xcb_atom_t, Vec<u8>)> {
+        read_property(self.connection, self.window, self.atoms.transfer, true)
+    }
+
+    // Gets the current server time, by making a zero-length change to a property and looking at the PropertyNotify.
+    // ICCCM says not to use CurrentTime when taking ownership of a selection, so that requests can't get mixed up.
+    unsafe fn timestamp(&mut self, route: &mut dyn FnMut(*mut xcb_generic_event_t)) -> Option<xcb_timestamp_t> {
+        let (window, property) = (self.window, self.atoms.transfer);
+        let _ = xcb_change_property(
+            self.connection,
+            XCB_PROP_MODE_APPEND,
+            window,
+            property,
+            XCB_ATOM_INTEGER,
+            32,
+            0,
+            ptr::null(),
+        );
+        self.wait_for(route, TIMEOUT, |ev| match (*ev).response_type & !(1 << 7) {
+            XCB_PROPERTY_NOTIFY => {
+                let event = &*(ev as *const xcb_property_notify_event_t);
+                let ours = event.window == window && event.atom == property;
+                (ours && event.state == XCB_PROPERTY_NEW_VALUE).then(|| event.time)
+            },
+            _ => None,
+        })
+    }
+
+    // Reads events until `f` returns `Some` for one of them, or until `timeout` passes. Events for our window which
+    // `f` doesn't want are processed as usual, and events for anything else are passed to `route`.
+    unsafe fn wait_for<T>(
+        &mut self,
+        route: &mut dyn FnMut(*mut xcb_generic_event_t),
+        timeout: Duration,
+        mut f: impl FnMut(*mut xcb_generic_event_t) -> Option<T>,
+    ) -> Option<T> {
+        let c = self.connection;
+        let deadline = Instant::now() + timeout;
+        let _ = xcb_flush(c);
+        loop {
+            let ev = xcb_poll_for_event(c);
+            if ev.is_null() {
+                let now = Instant::now();
+                if now >= deadline || xcb_connection_has_error(c) != 0 {
+                    return None
+                }
+                let mut fd = libc::pollfd { fd: xcb_get_file_descriptor(c), events: libc::POLLIN, revents: 0 };
+                let _ = libc::poll(&mut fd, 1, (deadline - now).as_millis() as c_int + 1);
+                continue
+            }
+            if let Some(result) = f(ev) {
+                free(ev.cast());
+                return Some(result)
+            }
+            if !self.process_event(ev) {
+                route(ev);
+            }
+        }
+    }
+
+    // The selections we can own, in the order `owned` is indexed by
+    fn selections(&self) -> [xcb_atom_t; 3] {
+        [self.atoms.clipboard, XCB_ATOM_PRIMARY, self.atoms.xdnd_selection]
+    }
+
+    fn atom(&self, selection: Selection) -> xcb_atom_t {
+        self.selections()[selection as usize]
+    }
+
+    fn index(&self, atom: xcb_atom_t) -> Option<usize> {
+        self.selections().iter().position(|&a| a == atom)
+    }
+
+    // Gets the window a selection-related event is destined for
+    unsafe fn event_window(&self, ev: *mut xcb_generic_event_t) -> Option<xcb_window_t> {
+        match (*ev).response_type & !(1 << 7) {
+            t if Some(t) == self.xfixes_event => Some((*(ev as *const xcb_xfixes_selection_notify_event_t)).window),
+            _ => event_window(ev),
+        }
+    }
+}
+
+// Asks XFixes to tell our window whenever the given selections change owner, returning the response type of its
+// SelectionNotify events, or `None` if it's not available
+unsafe fn select_owner_changes(
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    selections: &[xcb_atom_t],
+) -> Option<u8> {
+    libxcb_xfixes::load().ok()?;
+    let name = "XFIXES";
+    let cookie = xcb_query_extension(c, name.len() as _, name.as_ptr().cast());
+    let reply = xcb_query_extension_reply(c, cookie, ptr::null_mut());
+    if reply.is_null() {
+        return None
+    }
+    let (present, first_event) = ((*reply).present != 0, (*reply).first_event);
+    free(reply.cast());
+    if !present {
+        return None
+    }
+
+    // The server won't accept any XFixes requests until we've told it which version we speak
+    let version = xcb_xfixes_query_version_reply(c, xcb_xfixes_query_version(c, 5, 0), ptr::null_mut());
+    if version.is_null() {
+        return None
+    }
+    free(version);
+    let mask = XCB_XFIXES_SELECTION_EVENT_MASK_SET_SELECTION_OWNER
+        | XCB_XFIXES_SELECTION_EVENT_MASK_SELECTION_WINDOW_DESTROY
+        | XCB_XFIXES_SELECTION_EVENT_MASK_SELECTION_CLIENT_CLOSE;
+    for &selection in selections {
+        let _ = xcb_xfixes_select_selection_input(c, window, selection, mask);
+    }
+    Some(first_event + XCB_XFIXES_SELECTION_NOTIFY)
+}
+
+// Gets the window a core selection-related event is destined for
+unsafe fn event_window(ev: *mut xcb_generic_event_t) -> Option<xcb_window_t> {
+    match (*ev).response_type & !(1 << 7) {
+        XCB_SELECTION_REQUEST => Some((*(ev as *const xcb_selection_request_event_t)).owner),
+        XCB_SELECTION_CLEAR => Some((*(ev as *const xcb_selection_clear_event_t)).owner),
+        XCB_SELECTION_NOTIFY => Some((*(ev as *const xcb_selection_notify_event_t)).requestor),
+        XCB_PROPERTY_NOTIFY => Some((*(ev as *const xcb_property_notify_event_t)).window),
+        _ => None,
+    }
+}
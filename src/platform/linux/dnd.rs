@@ -0,0 +1,756 @@
+// Drag and drop through the XDND protocol (https://freedesktop.org/wiki/Specifications/XDND)
+//
+// Windows which accept drops say so with the XdndAware property. While something's dragged over one, the source sends
+// it XdndEnter with the formats on offer, XdndPosition whenever the pointer moves (which we answer with XdndStatus),
+// and finally either XdndLeave or XdndDrop. After a drop we ask for the data by converting the XdndSelection
+// selection, the same way as pasting from the clipboard, then tell the source we're done with XdndFinished.
+//
+// All of this is sent to the window itself, so it's handled along with the rest of that window's events. We never
+// block waiting for the source: the SelectionNotify with the data, and any INCR chunks after it, arrive as events too.
+//
+// Dragging out of our windows is the same the other way round. We grab the pointer so that we hear about it moving
+// over other clients' windows, look for the one under it with XdndAware, and send it the messages above ourselves.
+// The keyboard's grabbed too, so that Escape can cancel the drag. When more than one action is allowed we offer
+// XdndActionAsk along with the list of them, and the target can ask the user which one they want.
+// The data is served by the clipboard, which owns XdndSelection for us.
+
+use super::{ffi::*, x11::{atom_names, intern_atoms, read_property}};
+use crate::{dnd::{DragAction, DropData}, error::Error, event::Event};
+
+use std::{
+    ffi::OsString,
+    mem,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+    ptr,
+    time::{Duration, Instant},
+};
+
+// The version of the protocol we speak, which is the latest one
+const XDND_VERSION: u32 = 5;
+
+// How long we give a target to finish with a drop, which is long enough for the user to pick an action if it asks
+const FINISH_TIMEOUT: Duration = Duration::from_secs(30);
+
+// X keycodes are evdev codes + 8, and KEY_ESC is 1
+const ESCAPE_KEYCODE: u8 = 9;
+
+#[derive(Clone, Copy)]
+pub(super) struct DndAtoms {
+    aware: xcb_atom_t,
+    enter: xcb_atom_t,
+    position: xcb_atom_t,
+    status: xcb_atom_t,
+    leave: xcb_atom_t,
+    drop: xcb_atom_t,
+    finished: xcb_atom_t,
+    // Both the selection the data is transferred through, and the property we have it written to
+    selection: xcb_atom_t,
+    type_list: xcb_atom_t,
+    action_copy: xcb_atom_t,
+    action_move: xcb_atom_t,
+    action_link: xcb_atom_t,
+    action_ask: xcb_atom_t,
+    action_list: xcb_atom_t,
+    action_description: xcb_atom_t,
+    uri_list: xcb_atom_t,
+    incr: xcb_atom_t,
+}
+
+impl DndAtoms {
+    pub(super) unsafe fn new(connection: *mut xcb_connection_t) -> Result<Self, Error> {
+        let names = [
+            "XdndAware",
+            "XdndEnter",
+            "XdndPosition",
+            "XdndStatus",
+            "XdndLeave",
+            "XdndDrop",
+            "XdndFinished",
+            "XdndSelection",
+            "XdndTypeList",
+            "XdndActionCopy",
+            "XdndActionMove",
+            "XdndActionLink",
+            "XdndActionAsk",
+            "XdndActionList",
+            "XdndActionDescription",
+            "text/uri-list",
+            "INCR",
+        ];
+        let mut atoms = [0 as xcb_atom_t; 17];
+        intern_atoms(connection, &names, &mut atoms)?;
+        Ok(Self {
+            aware: atoms[0],
+            enter: atoms[1],
+            position: atoms[2],
+            status: atoms[3],
+            leave: atoms[4],
+            drop: atoms[5],
+            finished: atoms[6],
+            selection: atoms[7],
+            type_list: atoms[8],
+            action_copy: atoms[9],
+            action_move: atoms[10],
+            action_link: atoms[11],
+            action_ask: atoms[12],
+            action_list: atoms[13],
+            action_description: atoms[14],
+            uri_list: atoms[15],
+            incr: atoms[16],
+        })
+    }
+
+    fn action(&self, action: DragAction) -> xcb_atom_t {
+        match action {
+            DragAction::Copy => self.action_copy,
+            DragAction::Move => self.action_move,
+            DragAction::Link => self.action_link,
+        }
+    }
+
+    fn drag_action(&self, atom: xcb_atom_t) -> Option<DragAction> {
+        [DragAction::Copy, DragAction::Move, DragAction::Link].iter().copied().find(|&a| self.action(a) == atom)
+    }
+}
+
+// Tells drag sources that the window accepts drops
+pub(super) unsafe fn set_aware(c: *mut xcb_connection_t, atoms: &DndAtoms, window: xcb_window_t) {
+    let _ = xcb_change_property(
+        c,
+        XCB_PROP_MODE_REPLACE,
+        window,
+        atoms.aware,
+        XCB_ATOM_ATOM,
+        32,
+        1,
+        (&XDND_VERSION as *const u32).cast(),
+    );
+}
+
+// Tracks drags from other clients over a window which accepts drops
+#[derive(Default)]
+pub(super) struct DropTarget {
+    drag: Option<Drag>,
+}
+
+struct Drag {
+    source: xcb_window_t,
+    version: u32,
+    types: Vec<xcb_atom_t>,
+    // The last position we reported, or `None` if we haven't sent `DragEnter` yet
+    position: Option<(i16, i16)>,
+    // The action we last told the source we'd take
+    action: xcb_atom_t,
+    // Set once it's been dropped and we've asked for the data
+    transfer: Option<Transfer>,
+}
+
+struct Transfer {
+    target: xcb_atom_t,
+    // What we've received so far if the data is coming in chunks with INCR
+    incr: Option<Vec<u8>>,
+}
+
+impl DropTarget {
+    // Handles a client message sent to the window, if it's part of XDND. `origin` is the position of the window's
+    // inner drawable area relative to the root window, which is what positions are given relative to.
+    pub(super) unsafe fn client_message(
+        &mut self,
+        c: *mut xcb_connection_t,
+        atoms: &DndAtoms,
+        window: xcb_window_t,
+        origin: (i16, i16),
+        event: &xcb_client_message_event_t,
+        events: &mut Vec<Event>,
+    ) {
+        if event.format != 32 {
+            return
+        }
+        let data = event.client_data.data32;
+        let source = data[0];
+        if event.r#type == atoms.enter {
+            // A new drag replaces any which its source abandoned without telling us
+            if self.drag.take().map_or(false, |drag| drag.position.is_some()) {
+                events.push(Event::DragLeave);
+            }
+            // Only the first three types fit in the message, so if there are more the source lists them in a property
+            let types = if data[1] & 1 != 0 {
+                type_list(c, atoms, source)
+            } else {
+                data[2..].iter().copied().filter(|&atom| atom != XCB_ATOM_NONE).collect()
+            };
+            let version = data[1] >> 24;
+            let action = atoms.action_copy;
+            self.drag = Some(Drag { source, version, types, position: None, action, transfer: None });
+        } else if event.r#type == atoms.position {
+            let drag = match self.drag.as_mut() {
+                Some(drag) if drag.source == source && drag.transfer.is_none() => drag,
+                _ => return,
+            };
+            let position = (
+                ((data[2] >> 16) as i16).wrapping_sub(origin.0),
+                (data[2] as i16).wrapping_sub(origin.1),
+            );
+            match drag.position {
+                None => events.push(Event::DragEnter { position, mime_types: atom_names(c, &drag.types) }),
+                Some(last) if last != position => events.push(Event::DragMove { position }),
+                Some(_) => (),
+            }
+            drag.position = Some(position);
+            // We go along with whatever action the source proposes, since sources which only allow one take anything
+            // else as a refusal. We can't ask the user, though, so if it wants us to, or it's something we don't
+            // know, we copy.
+            drag.action = match atoms.drag_action(data[4]) {
+                Some(_) => data[4],
+                None => atoms.action_copy,
+            };
+            // We'll take anything anywhere, and we want to hear about every movement rather than giving the source a
+            // rectangle it can move within without telling us
+            send_message(c, source, atoms.status, [window, 0b11, 0, 0, drag.action]);
+        } else if event.r#type == atoms.leave {
+            if self.drag.as_ref().map_or(false, |drag| drag.source == source) {
+                self.finish(c, atoms, window, None, events);
+            }
+        } else if event.r#type == atoms.drop {
+            let drag = match self.drag.as_mut() {
+                Some(drag) if drag.source == source && drag.transfer.is_none() => drag,
+                _ => return,
+            };
+            let target = match drag.types.contains(&atoms.uri_list) {
+                true => Some(atoms.uri_list),
+                false => drag.types.first().copied(),
+            };
+            match (target, drag.position) {
+                (Some(target), Some(_)) => {
+                    // The timestamp is only included since version 1
+                    let time = if drag.version >= 1 { data[2] } else { XCB_CURRENT_TIME };
+                    let _ = xcb_convert_selection(c, window, atoms.selection, target, atoms.selection, time);
+                    let _ = xcb_flush(c);
+                    drag.transfer = Some(Transfer { target, incr: None });
+                },
+                _ => {
+                    // There's nothing we could ask for, so the drop fails straight away
+                    send_message(c, source, atoms.finished, [window, 0, XCB_ATOM_NONE, 0, 0]);
+                    self.finish(c, atoms, window, None, events);
+                },
+            }
+        }
+    }
+
+    // Handles a SelectionNotify sent to the window, which may be the answer to our request for dropped data
+    pub(super) unsafe fn selection_notify(
+        &mut self,
+        c: *mut xcb_connection_t,
+        atoms: &DndAtoms,
+        window: xcb_window_t,
+        event: &xcb_selection_notify_event_t,
+        events: &mut Vec<Event>,
+    ) {
+        let transfer = match self.drag.as_mut().and_then(|drag| drag.transfer.as_mut()) {
+            Some(transfer) if event.selection == atoms.selection && transfer.incr.is_none() => transfer,
+            _ => return,
+        };
+        if event.property == XCB_ATOM_NONE {
+            return self.finish(c, atoms, window, None, events)
+        }
+        // Deleting the property as we read it is what tells the source to go ahead with INCR
+        let data = match read_property(c, window, atoms.selection, true) {
+            Some((r#type, _)) if r#type == atoms.incr => {
+                transfer.incr = Some(Vec::new());
+                return
+            },
+            Some((_, data)) => drop_data(c, atoms, transfer.target, data),
+            None => None,
+        };
+        self.finish(c, atoms, window, data, events);
+    }
+
+    // Handles a PropertyNotify on the window, which may be the next chunk of dropped data being sent with INCR
+    pub(super) unsafe fn property_notify(
+        &mut self,
+        c: *mut xcb_connection_t,
+        atoms: &DndAtoms,
+        window: xcb_window_t,
+        event: &xcb_property_notify_event_t,
+        events: &mut Vec<Event>,
+    ) {
+        if event.atom != atoms.selection || event.state != XCB_PROPERTY_NEW_VALUE {
+            return
+        }
+        let (target, buffer) = match self.drag.as_mut().and_then(|drag| drag.transfer.as_mut()) {
+            Some(Transfer { target, incr: Some(buffer) }) => (*target, buffer),
+            _ => return,
+        };
+        // The transfer ends with an empty chunk
+        let data = match read_property(c, window, atoms.selection, true) {
+            Some((_, chunk)) if chunk.is_empty() => drop_data(c, atoms, target, mem::take(buffer)),
+            Some((_, chunk)) => return buffer.extend_from_slice(&chunk),
+            None => None,
+        };
+        self.finish(c, atoms, window, data, events);
+    }
+
+    // Ends the current drag, reporting it as dropped if we got its data, and tells the source we're done with it
+    unsafe fn finish(
+        &mut self,
+        c: *mut xcb_connection_t,
+        atoms: &DndAtoms,
+        window: xcb_window_t,
+        data: Option<DropData>,
+        events: &mut Vec<Event>,
+    ) {
+        let drag = match self.drag.take() {
+            Some(drag) => drag,
+            None => return,
+        };
+        let accepted = data.is_some();
+        if let Some(position) = drag.position {
+            events.push(match data {
+                Some(data) => Event::Drop { position, data },
+                None => Event::DragLeave,
+            });
+        }
+        // Sources only expect to hear back after a drop, and only look at whether we accepted it since version 5
+        if drag.transfer.is_some() {
+            let action = if accepted { drag.action } else { XCB_ATOM_NONE };
+            send_message(c, drag.source, atoms.finished, [window, accepted.into(), action, 0, 0]);
+        }
+    }
+}
+
+// A drag out of one of our windows, which lasts from when it's started until the target says it's finished with it
+pub(super) struct DragSource {
+    types: Vec<xcb_atom_t>,
+    // The actions we allow, with the one we'd prefer first
+    actions: Vec<xcb_atom_t>,
+    // The window under the pointer which accepts drops, and the version of the protocol we're speaking with it
+    target: Option<(xcb_window_t, u32)>,
+    // The action the target said it would take in its last XdndStatus, or `None` if it won't take the drop
+    accepted: Option<xcb_atom_t>,
+    // Targets answer each position with a status, and we don't send another position until they have, so this is
+    // set while we're waiting and holds the latest position in the meantime
+    waiting: bool,
+    pending: Option<((i16, i16), xcb_timestamp_t)>,
+    // Set when the pointer's released, after which we're waiting for the target to finish with the drop
+    dropped: Option<Instant>,
+    // The time of the release if it came while we were waiting for a status, in which case we hold off on dropping
+    // until we know whether the target will take it
+    deferred_drop: Option<xcb_timestamp_t>,
+}
+
+impl DragSource {
+    // Grabs the pointer and keyboard for a new drag, whose data the clipboard should already be offering in `types`
+    pub(super) unsafe fn start(
+        c: *mut xcb_connection_t,
+        atoms: &DndAtoms,
+        window: xcb_window_t,
+        types: Vec<xcb_atom_t>,
+        actions: &[DragAction],
+    ) -> Result<Self, Error> {
+        // This fails if another client has grabbed the pointer, or if the window isn't visible
+        let reply = xcb_grab_pointer_reply(c, xcb_grab_pointer(
+            c,
+            0,
+            window,
+            (XCB_EVENT_MASK_BUTTON_RELEASE | XCB_EVENT_MASK_POINTER_MOTION) as u16,
+            XCB_GRAB_MODE_ASYNC,
+            XCB_GRAB_MODE_ASYNC,
+            XCB_WINDOW_NONE,
+            XCB_CURSOR_NONE,
+            XCB_CURRENT_TIME,
+        ), ptr::null_mut());
+        if reply.is_null() {
+            return Err(Error::SystemResources)
+        }
+        let status = (*reply).status;
+        free(reply.cast());
+        if status != XCB_GRAB_STATUS_SUCCESS {
+            return Err(Error::Unknown)
+        }
+
+        // Same again for the keyboard, and a drag that can't be cancelled is no good, so we give up if we can't
+        let reply = xcb_grab_keyboard_reply(c, xcb_grab_keyboard(
+            c,
+            0,
+            window,
+            XCB_CURRENT_TIME,
+            XCB_GRAB_MODE_ASYNC,
+            XCB_GRAB_MODE_ASYNC,
+        ), ptr::null_mut());
+        let status = if reply.is_null() { None } else { Some((*reply).status) };
+        free(reply.cast());
+        if status != Some(XCB_GRAB_STATUS_SUCCESS) {
+            let _ = xcb_ungrab_pointer(c, XCB_CURRENT_TIME);
+            let _ = xcb_flush(c);
+            return Err(status.map_or(Error::SystemResources, |_| Error::Unknown))
+        }
+
+        // XdndEnter only has room for three types, so targets look for the rest here
+        if types.len() > 3 {
+            let _ = xcb_change_property(
+                c,
+                XCB_PROP_MODE_REPLACE,
+                window,
+                atoms.type_list,
+                XCB_ATOM_ATOM,
+                32,
+                types.len() as _,
+                types.as_ptr().cast(),
+            );
+        }
+        let actions = actions.iter().map(|&action| atoms.action(action)).collect::<Vec<_>>();
+        // Targets which ask the user what to do get the choices from here, along with names to show for them
+        if actions.len() > 1 {
+            let _ = xcb_change_property(
+                c,
+                XCB_PROP_MODE_REPLACE,
+                window,
+                atoms.action_list,
+                XCB_ATOM_ATOM,
+                32,
+                actions.len() as _,
+                actions.as_ptr().cast(),
+            );
+            let mut descriptions = Vec::new();
+            for &action in &actions {
+                let name = match atoms.drag_action(action) {
+                    Some(DragAction::Copy) => "Copy",
+                    Some(DragAction::Move) => "Move",
+                    Some(DragAction::Link) => "Link",
+                    None => "",
+                };
+                descriptions.extend_from_slice(name.as_bytes());
+                descriptions.push(0);
+            }
+            let _ = xcb_change_property(
+                c,
+                XCB_PROP_MODE_REPLACE,
+                window,
+                atoms.action_description,
+                XCB_ATOM_STRING,
+                8,
+                descriptions.len() as _,
+                descriptions.as_ptr().cast(),
+            );
+        }
+        let _ = xcb_flush(c);
+        Ok(Self {
+            types,
+            actions,
+            target: None,
+            accepted: None,
+            waiting: false,
+            pending: None,
+            dropped: None,
+            deferred_drop: None,
+        })
+    }
+
+    pub(super) fn is_dropped(&self) -> bool {
+        self.dropped.is_some()
+    }
+
+    // Handles the pointer and keyboard events we get from our grabs, and XDND messages sent to the window, returning
+    // whether the drag is over. `root` is the root window, which the pointer's position is relative to.
+    pub(super) unsafe fn process_event(
+        &mut self,
+        c: *mut xcb_connection_t,
+        atoms: &DndAtoms,
+        root: xcb_window_t,
+        window: xcb_window_t,
+        ev: *mut xcb_generic_event_t,
+        events: &mut Vec<Event>,
+    ) -> bool {
+        match (*ev).response_type & !(1 << 7) {
+            XCB_MOTION_NOTIFY if self.dropped.is_none() => {
+                let event = &*(ev as *const xcb_motion_notify_event_t);
+                self.motion(c, atoms, root, window, (event.root_x, event.root_y), event.time);
+                false
+            },
+            XCB_BUTTON_RELEASE if self.dropped.is_none() => {
+                let event = &*(ev as *const xcb_button_release_event_t);
+                let _ = xcb_ungrab_pointer(c, event.time);
+                let _ = xcb_ungrab_keyboard(c, event.time);
+                self.dropped = Some(Instant::now());
+                if self.waiting && self.target.is_some() {
+                    self.deferred_drop = Some(event.time);
+                    let _ = xcb_flush(c);
+                    false
+                } else {
+                    self.drop(c, atoms, window, event.time, events)
+                }
+            },
+            XCB_KEY_PRESS if self.dropped.is_none() => {
+                let event = &*(ev as *const xcb_key_press_event_t);
+                if event.detail != ESCAPE_KEYCODE {
+                    return false
+                }
+                let _ = xcb_ungrab_pointer(c, event.time);
+                let _ = xcb_ungrab_keyboard(c, event.time);
+                self.dropped = Some(Instant::now());
+                self.cancel(c, atoms, window, events)
+            },
+            XCB_CLIENT_MESSAGE => {
+                let event = &*(ev as *const xcb_client_message_event_t);
+                let data = event.client_data.data32;
+                let version = match self.target {
+                    Some((target, version)) if event.format == 32 && data[0] == target => version,
+                    _ => return false,
+                };
+                if event.r#type == atoms.status {
+                    // Targets can only pick one of the actions we allow, or ask the user if we said they could
+                    let action = data[4];
+                    let asked = action == atoms.action_ask && self.actions.len() > 1;
+                    self.accepted = (data[1] & 1 != 0 && (asked || self.actions.contains(&action))).then(|| action);
+                    self.waiting = false;
+                    match self.deferred_drop.take() {
+                        Some(time) => self.drop(c, atoms, window, time, events),
+                        None => {
+                            self.send_position(c, atoms, window);
+                            false
+                        },
+                    }
+                } else if event.r#type == atoms.finished && self.dropped.is_some() && self.deferred_drop.is_none() {
+                    // Only version 5 says whether the drop worked and what was done, before that we trust the status,
+                    // which can't tell us what the user picked if they were asked
+                    let action = if version >= 5 { (data[1] & 1 != 0).then(|| data[2]) } else { self.accepted };
+                    let action = action.filter(|action| self.actions.contains(action));
+                    events.push(Event::DragEnd { action: action.and_then(|action| atoms.drag_action(action)) });
+                    true
+                } else {
+                    false
+                }
+            },
+            _ => false,
+        }
+    }
+
+    // Gives up on a drop if the target hasn't finished with it in time, returning whether it did. Without this a
+    // target that's stopped answering would leave us waiting for it forever, and `DragEnd` would never arrive.
+    pub(super) unsafe fn check_timeout(
+        &mut self,
+        c: *mut xcb_connection_t,
+        atoms: &DndAtoms,
+        window: xcb_window_t,
+        events: &mut Vec<Event>,
+    ) -> bool {
+        if !self.dropped.map_or(false, |time| time.elapsed() >= FINISH_TIMEOUT) {
+            return false
+        }
+        // If it never answered our last position it hasn't been sent the drop yet, so it still needs to be told
+        // that we're leaving
+        if self.deferred_drop.take().is_some() {
+            return self.cancel(c, atoms, window, events)
+        }
+        events.push(Event::DragEnd { action: None });
+        true
+    }
+
+    // Drops onto the target if it said it would take it, or otherwise cancels the drag, returning whether it's over
+    unsafe fn drop(
+        &mut self,
+        c: *mut xcb_connection_t,
+        atoms: &DndAtoms,
+        window: xcb_window_t,
+        time: xcb_timestamp_t,
+        events: &mut Vec<Event>,
+    ) -> bool {
+        match (self.target, self.accepted) {
+            (Some((target, _)), Some(_)) => {
+                send_message(c, target, atoms.drop, [window, 0, time, 0, 0]);
+                false
+            },
+            _ => self.cancel(c, atoms, window, events),
+        }
+    }
+
+    // Leaves the target, if there is one, and ends the drag without anything being done
+    unsafe fn cancel(
+        &mut self,
+        c: *mut xcb_connection_t,
+        atoms: &DndAtoms,
+        window: xcb_window_t,
+        events: &mut Vec<Event>,
+    ) -> bool {
+        if let Some((target, _)) = self.target.take() {
+            send_message(c, target, atoms.leave, [window, 0, 0, 0, 0]);
+        }
+        let _ = xcb_flush(c);
+        events.push(Event::DragEnd { action: None });
+        true
+    }
+
+    // Tells whichever window is under the pointer where it is, entering and leaving windows as it moves between them
+    unsafe fn motion(
+        &mut self,
+        c: *mut xcb_connection_t,
+        atoms: &DndAtoms,
+        root: xcb_window_t,
+        window: xcb_window_t,
+        position: (i16, i16),
+        time: xcb_timestamp_t,
+    ) {
+        let target = find_target(c, atoms, root, position);
+        if target.map(|(target, _)| target) != self.target.map(|(target, _)| target) {
+            if let Some((old, _)) = self.target.take() {
+                send_message(c, old, atoms.leave, [window, 0, 0, 0, 0]);
+            }
+            self.accepted = None;
+            self.waiting = false;
+            if let Some((new, version)) = target {
+                let mut data = [window, version << 24 | (self.types.len() > 3) as u32, 0, 0, 0];
+                for (slot, &r#type) in data[2..].iter_mut().zip(&self.types) {
+                    *slot = r#type;
+                }
+                send_message(c, new, atoms.enter, data);
+                self.target = target;
+            }
+        }
+        self.pending = self.target.map(|_| (position, time));
+        self.send_position(c, atoms, window);
+    }
+
+    // Sends the latest position to the target, unless it hasn't answered the last one yet
+    unsafe fn send_position(&mut self, c: *mut xcb_connection_t, atoms: &DndAtoms, window: xcb_window_t) {
+        if self.waiting {
+            return
+        }
+        if let (Some((target, _)), Some(((x, y), time))) = (self.target, self.pending.take()) {
+            let position = u32::from(x as u16) << 16 | u32::from(y as u16);
+            // With more than one action to choose from, the target can ask the user which they want
+            let action = if self.actions.len() > 1 { atoms.action_ask } else { self.actions[0] };
+            send_message(c, target, atoms.position, [window, 0, position, time, action]);
+            self.waiting = true;
+        }
+    }
+}
+
+// Finds the window under the pointer which accepts drops, returning it along with the version of the protocol we'll
+// speak with it. This is usually a client window inside a window manager's frame, so we look down through the windows
+// under the pointer until we find one with XdndAware.
+unsafe fn find_target(
+    c: *mut xcb_connection_t,
+    atoms: &DndAtoms,
+    root: xcb_window_t,
+    (x, y): (i16, i16),
+) -> Option<(xcb_window_t, u32)> {
+    let mut window = root;
+    loop {
+        let cookie = xcb_translate_coordinates(c, root, window, x, y);
+        let reply = xcb_translate_coordinates_reply(c, cookie, ptr::null_mut());
+        if reply.is_null() {
+            return None
+        }
+        let child = (*reply).child;
+        free(reply.cast());
+        if child == XCB_WINDOW_NONE {
+            return None
+        }
+        if let Some((XCB_ATOM_ATOM, data)) = read_property(c, child, atoms.aware, false) {
+            let version = data.get(..4).map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))?;
+            // Versions before 3 work differently enough that nobody supports them anymore
+            return (version >= 3).then(|| (child, version.min(XDND_VERSION)))
+        }
+        window = child;
+    }
+}
+
+// Encodes paths as a text/uri-list (RFC 2483)
+pub(super) fn uri_list(paths: &[PathBuf]) -> Vec<u8> {
+    let current_dir = std::env::current_dir().unwrap_or_default();
+    let mut list = Vec::new();
+    for path in paths {
+        list.extend_from_slice(b"file://");
+        list.extend(percent_encode(&current_dir.join(path)));
+        list.extend_from_slice(b"\r\n");
+    }
+    list
+}
+
+// Escapes anything in a path that isn't allowed in a URI
+fn percent_encode(path: &Path) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    for &b in path.as_os_str().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~".contains(&b) {
+            encoded.push(b);
+        } else {
+            encoded.extend_from_slice(format!("%{:02X}", b).as_bytes());
+        }
+    }
+    encoded
+}
+
+// Reads the list of types offered by a source with more than three of them
+unsafe fn type_list(c: *mut xcb_connection_t, atoms: &DndAtoms, source: xcb_window_t) -> Vec<xcb_atom_t> {
+    match read_property(c, source, atoms.type_list, false) {
+        Some((XCB_ATOM_ATOM, data)) => {
+            data.chunks_exact(4).map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]])).collect()
+        },
+        _ => Vec::new(),
+    }
+}
+
+// Turns dropped data into what we report. Files come as a list of URIs, and anything else is passed on as it is.
+unsafe fn drop_data(
+    c: *mut xcb_connection_t,
+    atoms: &DndAtoms,
+    target: xcb_atom_t,
+    data: Vec<u8>,
+) -> Option<DropData> {
+    if target == atoms.uri_list {
+        let paths = parse_uri_list(&data);
+        if !paths.is_empty() {
+            return Some(DropData::Paths(paths))
+        }
+    }
+    let mime_type = atom_names(c, &[target]).pop()?;
+    Some(DropData::Data { mime_type, data })
+}
+
+// Gets the local files from a text/uri-list (RFC 2483), skipping comments and any other kinds of URI
+fn parse_uri_list(list: &[u8]) -> Vec<PathBuf> {
+    list.split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter_map(|uri| {
+            let uri = uri.strip_prefix(b"file://")?;
+            // Local files have either no host or localhost, anything else is on another machine
+            let (host, path) = uri.split_at(uri.iter().position(|&b| b == b'/')?);
+            (host.is_empty() || host == b"localhost").then(|| PathBuf::from(OsString::from_vec(percent_decode(path))))
+        })
+        .collect()
+}
+
+// Decodes %XX escapes in a URI
+fn percent_decode(s: &[u8]) -> Vec<u8> {
+    let hex = |i: usize| s.get(i).and_then(|&b| char::from(b).to_digit(16));
+    let mut decoded = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        match (s[i], hex(i + 1), hex(i + 2)) {
+            (b'%', Some(high), Some(low)) => {
+                decoded.push((high << 4 | low) as u8);
+                i += 3;
+            },
+            (b, _, _) => {
+                decoded.push(b);
+                i += 1;
+            },
+        }
+    }
+    decoded
+}
+
+// Sends an XDND message, which is a client message to the other client's window
+unsafe fn send_message(c: *mut xcb_connection_t, window: xcb_window_t, r#type: xcb_atom_t, data32: [u32; 5]) {
+    let event = xcb_client_message_event_t {
+        response_type: XCB_CLIENT_MESSAGE,
+        format: 32,
+        sequence: 0,
+        window,
+        r#type,
+        client_data: ClientData { data32 },
+    };
+    xcb_discard_reply(c, xcb_send_event_checked(c, false.into(), window, 0, (&event as *const _) as *const c_char));
+    let _ = xcb_flush(c);
+}
@@ -1,5 +1,5 @@
 #![allow(bad_style)]
-#![allow(clippy::too_many_arguments)]
+#![allow(clippy::too_many_arguments, clippy::upper_case_acronyms)]
 
 pub(super) use libc::{c_char, c_int, c_uint, c_void, free, getpid};
 #[cfg(feature = "input")]
@@ -16,9 +16,31 @@ load! {
         fn XDefaultScreen(display: *mut Display) -> c_int;
         fn XCloseDisplay(display: *mut Display) -> c_int;
         #[cfg(feature = "input")]
-        fn XLookupKeysym(event_struct: *mut XKeyEvent, index: c_int) -> KeySym;
+        fn XFlush(display: *mut Display) -> c_int;
         #[cfg(feature = "input")]
-        fn XLookupString(event_struct: *mut XKeyEvent, buffer_return: *mut c_char, bytes_buffer: c_int, keysym_return: *mut KeySym, status_in_out: *mut c_void) -> c_int;
+        fn XPending(display: *mut Display) -> c_int;
+        #[cfg(feature = "input")]
+        fn XNextEvent(display: *mut Display, event_return: *mut XEvent) -> c_int;
+        #[cfg(feature = "input")]
+        fn XFilterEvent(event: *mut XEvent, w: c_ulong) -> c_int;
+        #[cfg(feature = "input")]
+        fn XFree(data: *mut c_void) -> c_int;
+        #[cfg(feature = "input")]
+        fn XSetLocaleModifiers(modifier_list: *const c_char) -> *mut c_char;
+        #[cfg(feature = "input")]
+        fn XOpenIM(display: *mut Display, db: *mut c_void, res_name: *mut c_char, res_class: *mut c_char) -> XIM;
+        #[cfg(feature = "input")]
+        fn XCloseIM(im: XIM) -> c_int;
+        #[cfg(feature = "input")]
+        fn XDestroyIC(ic: XIC);
+        #[cfg(feature = "input")]
+        fn XSetICFocus(ic: XIC);
+        #[cfg(feature = "input")]
+        fn XUnsetICFocus(ic: XIC);
+        #[cfg(feature = "input")]
+        fn Xutf8ResetIC(ic: XIC) -> *mut c_char;
+        #[cfg(feature = "input")]
+        fn Xutf8LookupString(ic: XIC, event: *mut XKeyEvent, buffer_return: *mut c_char, bytes_buffer: c_int, keysym_return: *mut KeySym, status_return: *mut c_int) -> c_int;
     }
     pub(super) xlib_xcb(libX11_xcb) "libX11-xcb.so.1", "libX11-xcb.so" {
         fn XGetXCBConnection(dpy: *mut Display) -> *mut xcb_connection_t;
@@ -27,11 +49,15 @@ load! {
     pub(super) xcb(libxcb) "libxcb.so.1", "libxcb.so" {
         //fn xcb_connect(displayname: *const c_char, screenp: *mut c_int) -> *mut xcb_connection_t;
         fn xcb_connection_has_error(c: *mut xcb_connection_t) -> c_int;
+        fn xcb_get_file_descriptor(c: *mut xcb_connection_t) -> c_int;
         //fn xcb_disconnect(c: *mut xcb_connection_t);
         fn xcb_discard_reply(c: *mut xcb_connection_t, sequence: c_uint);
         fn xcb_get_setup(c: *mut xcb_connection_t) -> *const xcb_setup_t;
         fn xcb_setup_roots_iterator(R: *const xcb_setup_t) -> xcb_screen_iterator_t;
         fn xcb_screen_next(i: *mut xcb_screen_iterator_t);
+        fn xcb_screen_allowed_depths_iterator(R: *const xcb_screen_t) -> xcb_depth_iterator_t;
+        fn xcb_depth_next(i: *mut xcb_depth_iterator_t);
+        fn xcb_depth_visuals_iterator(R: *const xcb_depth_t) -> xcb_visualtype_iterator_t;
 
         fn xcb_flush(c: *mut xcb_connection_t) -> c_int;
         fn xcb_generate_id(c: *mut xcb_connection_t) -> u32;
@@ -68,6 +94,7 @@ load! {
             data_len: u32,
             data: *const c_void,
         ) -> c_uint;
+        fn xcb_delete_property(c: *mut xcb_connection_t, window: xcb_window_t, property: xcb_atom_t) -> c_uint;
         fn xcb_translate_coordinates(
             c: *mut xcb_connection_t,
             src: xcb_window_t,
@@ -80,6 +107,98 @@ load! {
             cookie: c_uint,
             err: *mut *mut xcb_generic_error_t,
         ) -> *mut xcb_translate_coordinates_reply_t;
+        fn xcb_grab_pointer(
+            c: *mut xcb_connection_t,
+            owner_events: u8,
+            grab_window: xcb_window_t,
+            event_mask: u16,
+            pointer_mode: u8,
+            keyboard_mode: u8,
+            confine_to: xcb_window_t,
+            cursor: xcb_cursor_t,
+            time: xcb_timestamp_t,
+        ) -> c_uint;
+        fn xcb_grab_pointer_reply(
+            c: *mut xcb_connection_t,
+            cookie: c_uint,
+            e: *mut *mut xcb_generic_error_t,
+        ) -> *mut xcb_grab_pointer_reply_t;
+        fn xcb_ungrab_pointer(c: *mut xcb_connection_t, time: xcb_timestamp_t) -> c_uint;
+        fn xcb_grab_keyboard(
+            c: *mut xcb_connection_t,
+            owner_events: u8,
+            grab_window: xcb_window_t,
+            time: xcb_timestamp_t,
+            pointer_mode: u8,
+            keyboard_mode: u8,
+        ) -> c_uint;
+        fn xcb_grab_keyboard_reply(
+            c: *mut xcb_connection_t,
+            cookie: c_uint,
+            e: *mut *mut xcb_generic_error_t,
+        ) -> *mut xcb_grab_keyboard_reply_t;
+        fn xcb_ungrab_keyboard(c: *mut xcb_connection_t, time: xcb_timestamp_t) -> c_uint;
+        fn xcb_query_pointer(c: *mut xcb_connection_t, window: xcb_window_t) -> c_uint;
+        fn xcb_query_pointer_reply(
+            c: *mut xcb_connection_t,
+            cookie: c_uint,
+            e: *mut *mut xcb_generic_error_t,
+        ) -> *mut xcb_query_pointer_reply_t;
+        fn xcb_warp_pointer(
+            c: *mut xcb_connection_t,
+            src_window: xcb_window_t,
+            dst_window: xcb_window_t,
+            src_x: i16,
+            src_y: i16,
+            src_width: u16,
+            src_height: u16,
+            dst_x: i16,
+            dst_y: i16,
+        ) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_create_pixmap(
+            c: *mut xcb_connection_t,
+            depth: u8,
+            pid: xcb_pixmap_t,
+            drawable: xcb_drawable_t,
+            width: u16,
+            height: u16,
+        ) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_free_pixmap(c: *mut xcb_connection_t, pixmap: xcb_pixmap_t) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_create_gc(
+            c: *mut xcb_connection_t,
+            cid: xcb_gcontext_t,
+            drawable: xcb_drawable_t,
+            value_mask: u32,
+            value_list: *const c_void,
+        ) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_free_gc(c: *mut xcb_connection_t, gc: xcb_gcontext_t) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_poly_fill_rectangle(
+            c: *mut xcb_connection_t,
+            drawable: xcb_drawable_t,
+            gc: xcb_gcontext_t,
+            rectangles_len: u32,
+            rectangles: *const xcb_rectangle_t,
+        ) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_create_cursor(
+            c: *mut xcb_connection_t,
+            cid: xcb_cursor_t,
+            source: xcb_pixmap_t,
+            mask: xcb_pixmap_t,
+            fore_red: u16,
+            fore_green: u16,
+            fore_blue: u16,
+            back_red: u16,
+            back_green: u16,
+            back_blue: u16,
+            x: u16,
+            y: u16,
+        ) -> c_uint;
         fn xcb_map_window(c: *mut xcb_connection_t, window: xcb_window_t) -> c_uint;
         fn xcb_unmap_window(c: *mut xcb_connection_t, window: xcb_window_t) -> c_uint;
         fn xcb_intern_atom(
@@ -97,29 +216,129 @@ load! {
         fn xcb_poll_for_queued_event(c: *mut xcb_connection_t) -> *mut xcb_generic_event_t;
         fn xcb_send_event_checked(c: *mut xcb_connection_t, propagate: u8, destination: xcb_window_t, event_mask: u32, event: *const c_char) -> c_uint;
         fn xcb_destroy_window(c: *mut xcb_connection_t, xid: xcb_window_t) -> c_uint;
+        fn xcb_set_selection_owner(c: *mut xcb_connection_t, owner: xcb_window_t, selection: xcb_atom_t, time: xcb_timestamp_t) -> c_uint;
+        fn xcb_get_selection_owner(c: *mut xcb_connection_t, selection: xcb_atom_t) -> c_uint;
+        fn xcb_get_selection_owner_reply(c: *mut xcb_connection_t, cookie: c_uint, e: *mut *mut xcb_generic_error_t) -> *mut xcb_get_selection_owner_reply_t;
+        fn xcb_get_maximum_request_length(c: *mut xcb_connection_t) -> u32;
+        fn xcb_change_window_attributes(c: *mut xcb_connection_t, window: xcb_window_t, value_mask: u32, value_list: *const c_void) -> c_uint;
+        fn xcb_get_atom_name(c: *mut xcb_connection_t, atom: xcb_atom_t) -> c_uint;
+        fn xcb_get_atom_name_reply(c: *mut xcb_connection_t, cookie: c_uint, e: *mut *mut xcb_generic_error_t) -> *mut xcb_get_atom_name_reply_t;
+        fn xcb_get_atom_name_name(reply: *const xcb_get_atom_name_reply_t) -> *mut c_char;
+        fn xcb_get_atom_name_name_length(reply: *const xcb_get_atom_name_reply_t) -> c_int;
+        fn xcb_convert_selection(c: *mut xcb_connection_t, requestor: xcb_window_t, selection: xcb_atom_t, target: xcb_atom_t, property: xcb_atom_t, time: xcb_timestamp_t) -> c_uint;
         fn xcb_get_property(c: *mut xcb_connection_t, delete: u8, window: xcb_window_t, property: xcb_atom_t, r#type: xcb_atom_t, long_offset: u32, long_length: u32) -> c_uint;
         fn xcb_get_property_reply(c: *mut xcb_connection_t, sequence: c_uint, e: *mut *mut xcb_generic_error_t) -> *mut xcb_get_property_reply_t;
         fn xcb_get_property_value(reply: *const xcb_get_property_reply_t) -> *mut c_void;
         fn xcb_get_property_value_length(reply: *const xcb_get_property_reply_t) -> c_int;
-        #[cfg(feature = "input")]
         fn xcb_query_extension(c: *mut xcb_connection_t, name_len: u16, name: *const c_char) -> c_uint;
-        #[cfg(feature = "input")]
         fn xcb_query_extension_reply(c: *mut xcb_connection_t, sequence: c_uint, e: *mut *mut xcb_generic_error_t) -> *mut xcb_query_extension_reply_t;
     }
+    pub(super) xfixes(libxcb_xfixes) "libxcb-xfixes.so.0", "libxcb-xfixes.so" {
+        fn xcb_xfixes_query_version(c: *mut xcb_connection_t, client_major_version: u32, client_minor_version: u32) -> c_uint;
+        fn xcb_xfixes_query_version_reply(c: *mut xcb_connection_t, cookie: c_uint, e: *mut *mut xcb_generic_error_t) -> *mut c_void;
+        fn xcb_xfixes_select_selection_input(c: *mut xcb_connection_t, window: xcb_window_t, selection: xcb_atom_t, event_mask: u32) -> c_uint;
+    }
     #[cfg(feature = "input")]
     pub(super) xinput(libxcb_xinput) "libxcb-xinput.so.0", "libxcb-xinput.so" {
         #[cfg(feature = "input")]
         fn xcb_input_xi_select_events_checked(c: *mut xcb_connection_t, window: xcb_window_t, num_mask: u16, masks: *mut xcb_input_event_mask_t) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_input_xi_query_version(c: *mut xcb_connection_t, major_version: u16, minor_version: u16) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_input_xi_query_version_reply(c: *mut xcb_connection_t, cookie: c_uint, e: *mut *mut xcb_generic_error_t) -> *mut xcb_input_xi_query_version_reply_t;
+        #[cfg(feature = "input")]
+        fn xcb_input_xi_get_client_pointer(c: *mut xcb_connection_t, window: xcb_window_t) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_input_xi_get_client_pointer_reply(c: *mut xcb_connection_t, cookie: c_uint, e: *mut *mut xcb_generic_error_t) -> *mut xcb_input_xi_get_client_pointer_reply_t;
+        #[cfg(feature = "input")]
+        fn xcb_input_xi_query_pointer(c: *mut xcb_connection_t, window: xcb_window_t, deviceid: xcb_input_device_id_t) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_input_xi_query_device(c: *mut xcb_connection_t, deviceid: xcb_input_device_id_t) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_input_xi_query_device_reply(c: *mut xcb_connection_t, cookie: c_uint, e: *mut *mut xcb_generic_error_t) -> *mut xcb_input_xi_query_device_reply_t;
+        #[cfg(feature = "input")]
+        fn xcb_input_xi_query_pointer_reply(c: *mut xcb_connection_t, cookie: c_uint, e: *mut *mut xcb_generic_error_t) -> *mut xcb_input_xi_query_pointer_reply_t;
     }
     #[cfg(feature = "input")]
     pub(super) xkbcommon(libxkbcommon) "libxkbcommon.so.0", "libxkbcommon.so" {
         #[cfg(feature = "input")]
-        fn xkb_keysym_to_utf32(keysym: u32) -> u32;
+        fn xkb_keysym_to_utf32(keysym: xkb_keysym_t) -> u32;
+        #[cfg(feature = "input")]
+        fn xkb_keysym_get_name(keysym: xkb_keysym_t, buffer: *mut c_char, size: usize) -> c_int;
+        #[cfg(feature = "input")]
+        fn xkb_keymap_unref(keymap: *mut xkb_keymap);
+        #[cfg(feature = "input")]
+        fn xkb_keymap_min_keycode(keymap: *mut xkb_keymap) -> xkb_keycode_t;
+        #[cfg(feature = "input")]
+        fn xkb_keymap_max_keycode(keymap: *mut xkb_keymap) -> xkb_keycode_t;
+        #[cfg(feature = "input")]
+        fn xkb_keymap_num_levels_for_key(keymap: *mut xkb_keymap, key: xkb_keycode_t, layout: xkb_layout_index_t) -> xkb_level_index_t;
+        #[cfg(feature = "input")]
+        fn xkb_keymap_num_layouts(keymap: *mut xkb_keymap) -> xkb_layout_index_t;
+        #[cfg(feature = "input")]
+        fn xkb_keymap_layout_get_name(keymap: *mut xkb_keymap, idx: xkb_layout_index_t) -> *const c_char;
+        #[cfg(feature = "input")]
+        fn xkb_keymap_key_get_syms_by_level(keymap: *mut xkb_keymap, key: xkb_keycode_t, layout: xkb_layout_index_t, level: xkb_level_index_t, syms_out: *mut *const xkb_keysym_t) -> c_int;
+        #[cfg(feature = "input")]
+        fn xkb_state_unref(state: *mut xkb_state);
+        #[cfg(feature = "input")]
+        fn xkb_state_update_mask(state: *mut xkb_state, depressed_mods: xkb_mod_mask_t, latched_mods: xkb_mod_mask_t, locked_mods: xkb_mod_mask_t, depressed_layout: xkb_layout_index_t, latched_layout: xkb_layout_index_t, locked_layout: xkb_layout_index_t) -> c_int;
+        #[cfg(feature = "input")]
+        fn xkb_state_key_get_one_sym(state: *mut xkb_state, key: xkb_keycode_t) -> xkb_keysym_t;
+        #[cfg(feature = "input")]
+        fn xkb_state_serialize_layout(state: *mut xkb_state, components: c_int) -> xkb_layout_index_t;
+        #[cfg(feature = "input")]
+        fn xkb_state_key_get_layout(state: *mut xkb_state, key: xkb_keycode_t) -> xkb_layout_index_t;
+        #[cfg(feature = "input")]
+        fn xkb_context_new(flags: c_int) -> *mut xkb_context;
+        #[cfg(feature = "input")]
+        fn xkb_context_unref(context: *mut xkb_context);
+        #[cfg(feature = "input")]
+        fn xkb_compose_table_new_from_locale(context: *mut xkb_context, locale: *const c_char, flags: c_int) -> *mut xkb_compose_table;
+        #[cfg(feature = "input")]
+        fn xkb_compose_table_unref(table: *mut xkb_compose_table);
+        #[cfg(feature = "input")]
+        fn xkb_compose_state_new(table: *mut xkb_compose_table, flags: c_int) -> *mut xkb_compose_state;
+        #[cfg(feature = "input")]
+        fn xkb_compose_state_unref(state: *mut xkb_compose_state);
+        #[cfg(feature = "input")]
+        fn xkb_compose_state_reset(state: *mut xkb_compose_state);
+        #[cfg(feature = "input")]
+        fn xkb_compose_state_feed(state: *mut xkb_compose_state, keysym: xkb_keysym_t) -> c_int;
+        #[cfg(feature = "input")]
+        fn xkb_compose_state_get_status(state: *mut xkb_compose_state) -> c_int;
+        #[cfg(feature = "input")]
+        fn xkb_compose_state_get_one_sym(state: *mut xkb_compose_state) -> xkb_keysym_t;
+        #[cfg(feature = "input")]
+        fn xkb_compose_state_get_utf8(state: *mut xkb_compose_state, buffer: *mut c_char, size: usize) -> c_int;
+    }
+    // This was only added in libxkbcommon 1.0, so it's optional
+    #[cfg(feature = "input")]
+    pub(super) xkbcommon_mods(libxkbcommon_mods) "libxkbcommon.so.0", "libxkbcommon.so" {
+        #[cfg(feature = "input")]
+        fn xkb_keymap_key_get_mods_for_level(keymap: *mut xkb_keymap, key: xkb_keycode_t, layout: xkb_layout_index_t, level: xkb_level_index_t, masks_out: *mut xkb_mod_mask_t, masks_size: usize) -> usize;
+    }
+    #[cfg(feature = "input")]
+    pub(super) xkbcommon_x11(libxkbcommon_x11) "libxkbcommon-x11.so.0", "libxkbcommon-x11.so" {
+        #[cfg(feature = "input")]
+        fn xkb_x11_setup_xkb_extension(connection: *mut xcb_connection_t, major_xkb_version: u16, minor_xkb_version: u16, flags: c_int, major_xkb_version_out: *mut u16, minor_xkb_version_out: *mut u16, base_event_out: *mut u8, base_error_out: *mut u8) -> c_int;
+        #[cfg(feature = "input")]
+        fn xkb_x11_get_core_keyboard_device_id(connection: *mut xcb_connection_t) -> i32;
+        #[cfg(feature = "input")]
+        fn xkb_x11_keymap_new_from_device(context: *mut xkb_context, connection: *mut xcb_connection_t, device_id: i32, flags: c_int) -> *mut xkb_keymap;
+        #[cfg(feature = "input")]
+        fn xkb_x11_state_new_from_device(keymap: *mut xkb_keymap, connection: *mut xcb_connection_t, device_id: i32) -> *mut xkb_state;
+    }
+    #[cfg(feature = "input")]
+    pub(super) xkb(libxcb_xkb) "libxcb-xkb.so.1", "libxcb-xkb.so" {
+        #[cfg(feature = "input")]
+        fn xcb_xkb_select_events(c: *mut xcb_connection_t, device_spec: u16, affect_which: u16, clear: u16, select_all: u16, affect_map: u16, map: u16, details: *const c_void) -> c_uint;
+        #[cfg(feature = "input")]
+        fn xcb_xkb_latch_lock_state(c: *mut xcb_connection_t, device_spec: u16, affect_mod_locks: u8, mod_locks: u8, lock_group: u8, group_lock: u8, affect_mod_latches: u8, latch_group: u8, group_latch: u16) -> c_uint;
     }
 }
 
 pub enum Display {}
-pub(super) enum xcb_setup_t {}
 pub(super) enum xcb_connection_t {}
 
 #[repr(C)]
@@ -127,15 +346,25 @@ pub(super) enum xcb_connection_t {}
 pub(super) enum EventQueueOwner { XlibOwnsEventQueue = 0, XCBOwnsEventQueue }
 pub(super) type xcb_atom_t = u32;
 pub(super) type xcb_colormap_t = u32;
+pub(super) type xcb_cursor_t = u32;
+#[cfg(feature = "input")]
+pub(super) type xcb_drawable_t = u32;
+#[cfg(feature = "input")]
+pub(super) type xcb_gcontext_t = u32;
+#[cfg(feature = "input")]
+pub(super) type xcb_pixmap_t = u32;
 pub(super) type xcb_visualid_t = u32;
 pub type xcb_window_t = u32;
 
 pub(super) const XCB_WINDOW_CLASS_INPUT_OUTPUT: u16 = 1;
+pub(super) const XCB_WINDOW_CLASS_INPUT_ONLY: u16 = 2;
 pub(super) const XCB_COPY_FROM_PARENT: u8 = 0;
-// pub(super) const XCB_KEY_PRESS: u8 = 2;
+pub(super) const XCB_VISUAL_CLASS_TRUE_COLOR: u8 = 4;
+pub(super) const XCB_KEY_PRESS: u8 = 2;
 // pub(super) const XCB_KEY_RELEASE: u8 = 3;
 // pub(super) const XCB_BUTTON_PRESS: u8 = 4;
-// pub(super) const XCB_BUTTON_RELEASE: u8 = 5;
+pub(super) const XCB_BUTTON_RELEASE: u8 = 5;
+pub(super) const XCB_MOTION_NOTIFY: u8 = 6;
 pub(super) const XCB_FOCUS_IN: u8 = 9;
 pub(super) const XCB_FOCUS_OUT: u8 = 10;
 pub(super) const XCB_UNMAP_NOTIFY: u8 = 18;
@@ -143,25 +372,53 @@ pub(super) const XCB_MAP_NOTIFY: u8 = 19;
 pub(super) const XCB_REPARENT_NOTIFY: u8 = 21;
 pub(super) const XCB_CONFIGURE_NOTIFY: u8 = 22;
 pub(super) const XCB_PROPERTY_NOTIFY: u8 = 28;
+pub(super) const XCB_SELECTION_CLEAR: u8 = 29;
+pub(super) const XCB_SELECTION_REQUEST: u8 = 30;
+pub(super) const XCB_SELECTION_NOTIFY: u8 = 31;
 pub(super) const XCB_CLIENT_MESSAGE: u8 = 33;
 #[cfg(feature = "input")]
 pub(super) const XCB_GE_GENERIC: u8 = 35;
 
+pub(super) const XCB_CURRENT_TIME: xcb_timestamp_t = 0;
+pub(super) const XCB_CURSOR_NONE: xcb_cursor_t = 0;
+pub(super) const XCB_GRAB_MODE_ASYNC: u8 = 1;
+pub(super) const XCB_GRAB_STATUS_SUCCESS: u8 = 0;
+
+#[cfg(feature = "input")]
+pub(super) const XCB_MOD_MASK_SHIFT: u32 = 1;
+#[cfg(feature = "input")]
+pub(super) const XCB_MOD_MASK_LOCK: u32 = 2;
+#[cfg(feature = "input")]
+pub(super) const XCB_MOD_MASK_CONTROL: u32 = 4;
+#[cfg(feature = "input")]
+pub(super) const XCB_MOD_MASK_1: u32 = 8;
+#[cfg(feature = "input")]
+pub(super) const XCB_MOD_MASK_2: u32 = 16;
+#[cfg(feature = "input")]
+pub(super) const XCB_MOD_MASK_4: u32 = 64;
+
 pub(super) const XCB_PROP_MODE_REPLACE: u8 = 0;
-//pub(super) const XCB_PROP_MODE_APPEND: u8 = 1;
+pub(super) const XCB_PROP_MODE_APPEND: u8 = 1;
 //pub(super) const XCB_PROP_MODE_PREPEND: u8 = 2;
 
-//pub(super) const XCB_ATOM_NONE: xcb_atom_t = 0;
+pub(super) const XCB_WINDOW_NONE: xcb_window_t = 0;
+
+pub(super) const XCB_ATOM_NONE: xcb_atom_t = 0;
+pub(super) const XCB_ATOM_PRIMARY: xcb_atom_t = 1;
 pub(super) const XCB_ATOM_ATOM: xcb_atom_t = 4;
 pub(super) const XCB_ATOM_CARDINAL: xcb_atom_t = 6;
+pub(super) const XCB_ATOM_INTEGER: xcb_atom_t = 19;
 pub(super) const XCB_ATOM_STRING: xcb_atom_t = 31;
 pub(super) const XCB_ATOM_WM_NAME: xcb_atom_t = 39;
 pub(super) const XCB_ATOM_WM_CLASS: xcb_atom_t = 67;
 
+pub(super) const XCB_CW_BORDER_PIXEL: u32 = 8;
 pub(super) const XCB_CW_COLORMAP: u32 = 8192;
 pub(super) const XCB_CW_EVENT_MASK: u32 = 2048;
 #[cfg(feature = "input")]
 pub(super) const XCB_EVENT_MASK_BUTTON_PRESS: u32 = 4;
+pub(super) const XCB_EVENT_MASK_BUTTON_RELEASE: u32 = 8;
+pub(super) const XCB_EVENT_MASK_POINTER_MOTION: u32 = 64;
 pub(super) const XCB_EVENT_MASK_STRUCTURE_NOTIFY: u32 = 131072;
 pub(super) const XCB_EVENT_MASK_SUBSTRUCTURE_NOTIFY: u32 = 524288;
 pub(super) const XCB_EVENT_MASK_SUBSTRUCTURE_REDIRECT: u32 = 1048576;
@@ -178,6 +435,21 @@ pub(super) const ICCCM_SIZE_HINT_P_MIN_SIZE: u32 = 1 << 4;
 pub(super) const ICCCM_SIZE_HINT_P_MAX_SIZE: u32 = 1 << 5;
 pub(super) const ICCCM_SIZE_HINT_BASE_SIZE: u32 = 1 << 8;
 
+#[repr(C)]
+pub(super) struct xcb_depth_t {
+    pub(super) depth: u8,
+    pub(super) _pad0: u8,
+    pub(super) visuals_len: u16,
+    pub(super) _pad1: [u8; 4],
+}
+
+#[repr(C)]
+pub(super) struct xcb_depth_iterator_t {
+    pub(super) data: *mut xcb_depth_t,
+    pub(super) rem: c_int,
+    pub(super) index: c_int,
+}
+
 #[repr(C)]
 pub(super) struct xcb_generic_error_t {
     pub(super) response_type: u8,
@@ -191,6 +463,15 @@ pub(super) struct xcb_generic_error_t {
     pub(super) full_sequence: u32,
 }
 
+#[cfg(feature = "input")]
+#[repr(C)]
+pub(super) struct xcb_rectangle_t {
+    pub(super) x: i16,
+    pub(super) y: i16,
+    pub(super) width: u16,
+    pub(super) height: u16,
+}
+
 #[repr(C)]
 pub(super) struct xcb_screen_iterator_t {
     pub(super) data: *mut xcb_screen_t,
@@ -220,6 +501,19 @@ pub(super) struct xcb_size_hints_t {
     pub(super) win_gravity: u32,
 }
 
+// There's more after this, but it's variable-length and we don't need it
+#[repr(C)]
+pub(super) struct xcb_setup_t {
+    pub(super) status: u8,
+    pub(super) _pad0: u8,
+    pub(super) protocol_major_version: u16,
+    pub(super) protocol_minor_version: u16,
+    pub(super) length: u16,
+    pub(super) release_number: u32,
+    pub(super) resource_id_base: u32,
+    pub(super) resource_id_mask: u32,
+}
+
 #[repr(C)]
 pub(super) struct xcb_screen_t {
     pub(super) root: xcb_window_t,
@@ -240,6 +534,25 @@ pub(super) struct xcb_screen_t {
     pub(super) allowed_depths_len: u8,
 }
 
+#[repr(C)]
+pub(super) struct xcb_visualtype_t {
+    pub(super) visual_id: xcb_visualid_t,
+    pub(super) class: u8,
+    pub(super) bits_per_rgb_value: u8,
+    pub(super) colormap_entries: u16,
+    pub(super) red_mask: u32,
+    pub(super) green_mask: u32,
+    pub(super) blue_mask: u32,
+    pub(super) _pad0: [u8; 4],
+}
+
+#[repr(C)]
+pub(super) struct xcb_visualtype_iterator_t {
+    pub(super) data: *mut xcb_visualtype_t,
+    pub(super) rem: c_int,
+    pub(super) index: c_int,
+}
+
 #[repr(C)]
 pub(super) struct xcb_translate_coordinates_reply_t {
     pub(super) response_type: u8,
@@ -251,6 +564,32 @@ pub(super) struct xcb_translate_coordinates_reply_t {
     pub(super) dst_y: i16,
 }
 
+#[repr(C)]
+pub(super) struct xcb_grab_pointer_reply_t {
+    pub(super) response_type: u8,
+    pub(super) status: u8,
+    pub(super) sequence: u16,
+    pub(super) length: u32,
+}
+
+pub(super) type xcb_grab_keyboard_reply_t = xcb_grab_pointer_reply_t;
+
+#[repr(C)]
+pub(super) struct xcb_query_pointer_reply_t {
+    pub(super) response_type: u8,
+    pub(super) same_screen: u8,
+    pub(super) sequence: u16,
+    pub(super) length: u32,
+    pub(super) root: xcb_window_t,
+    pub(super) child: xcb_window_t,
+    pub(super) root_x: i16,
+    pub(super) root_y: i16,
+    pub(super) win_x: i16,
+    pub(super) win_y: i16,
+    pub(super) mask: u16,
+    pub(super) _pad0: [u8; 2],
+}
+
 #[repr(C)]
 pub(super) struct xcb_intern_atom_reply_t {
     pub(super) response_type: u8,
@@ -272,7 +611,6 @@ pub(super) struct xcb_get_property_reply_t {
     pub(super) _pad0: [u8; 12],
 }
 
-#[cfg(feature = "input")]
 #[repr(C)]
 pub(super) struct xcb_query_extension_reply_t {
     pub(super) response_type: u8,
@@ -304,6 +642,27 @@ pub(super) struct xcb_client_message_event_t {
     pub(super) client_data: ClientData,
 }
 
+#[repr(C)]
+pub(super) struct xcb_button_release_event_t {
+    pub(super) response_type: u8,
+    pub(super) detail: u8,
+    pub(super) sequence: u16,
+    pub(super) time: xcb_timestamp_t,
+    pub(super) root: xcb_window_t,
+    pub(super) event: xcb_window_t,
+    pub(super) child: xcb_window_t,
+    pub(super) root_x: i16,
+    pub(super) root_y: i16,
+    pub(super) event_x: i16,
+    pub(super) event_y: i16,
+    pub(super) state: u16,
+    pub(super) same_screen: u8,
+    pub(super) _pad0: u8,
+}
+
+pub(super) type xcb_motion_notify_event_t = xcb_button_release_event_t;
+pub(super) type xcb_key_press_event_t = xcb_button_release_event_t;
+
 #[repr(C)]
 pub(super) struct xcb_focus_in_event_t {
     pub(super) response_type: u8,
@@ -380,9 +739,82 @@ pub(super) struct xcb_property_notify_event_t {
 }
 pub(super) const XCB_PROPERTY_NEW_VALUE: u8 = 0;
 pub(super) const XCB_PROPERTY_DELETE: u8 = 1;
+pub(super) const XCB_GET_PROPERTY_TYPE_ANY: xcb_atom_t = 0;
 pub(super) const XCB_ATOM_WM_NORMAL_HINTS: u32 = 40;
 pub(super) const XCB_ATOM_WM_SIZE_HINTS: u32 = 41;
 
+#[repr(C)]
+pub(super) struct xcb_selection_clear_event_t {
+    pub(super) response_type: u8,
+    pub(super) _pad0: u8,
+    pub(super) sequence: u16,
+    pub(super) time: xcb_timestamp_t,
+    pub(super) owner: xcb_window_t,
+    pub(super) selection: xcb_atom_t,
+}
+
+#[repr(C)]
+pub(super) struct xcb_selection_request_event_t {
+    pub(super) response_type: u8,
+    pub(super) _pad0: u8,
+    pub(super) sequence: u16,
+    pub(super) time: xcb_timestamp_t,
+    pub(super) owner: xcb_window_t,
+    pub(super) requestor: xcb_window_t,
+    pub(super) selection: xcb_atom_t,
+    pub(super) target: xcb_atom_t,
+    pub(super) property: xcb_atom_t,
+}
+
+#[repr(C)]
+pub(super) struct xcb_selection_notify_event_t {
+    pub(super) response_type: u8,
+    pub(super) _pad0: u8,
+    pub(super) sequence: u16,
+    pub(super) time: xcb_timestamp_t,
+    pub(super) requestor: xcb_window_t,
+    pub(super) selection: xcb_atom_t,
+    pub(super) target: xcb_atom_t,
+    pub(super) property: xcb_atom_t,
+}
+
+pub(super) const XCB_XFIXES_SELECTION_NOTIFY: u8 = 0;
+pub(super) const XCB_XFIXES_SELECTION_EVENT_MASK_SET_SELECTION_OWNER: u32 = 1;
+pub(super) const XCB_XFIXES_SELECTION_EVENT_MASK_SELECTION_WINDOW_DESTROY: u32 = 2;
+pub(super) const XCB_XFIXES_SELECTION_EVENT_MASK_SELECTION_CLIENT_CLOSE: u32 = 4;
+
+#[repr(C)]
+pub(super) struct xcb_xfixes_selection_notify_event_t {
+    pub(super) response_type: u8,
+    pub(super) subtype: u8,
+    pub(super) sequence: u16,
+    pub(super) window: xcb_window_t,
+    pub(super) owner: xcb_window_t,
+    pub(super) selection: xcb_atom_t,
+    pub(super) timestamp: xcb_timestamp_t,
+    pub(super) selection_timestamp: xcb_timestamp_t,
+    pub(super) _pad0: [u8; 8],
+}
+
+#[repr(C)]
+pub(super) struct xcb_get_atom_name_reply_t {
+    pub(super) response_type: u8,
+    pub(super) _pad0: u8,
+    pub(super) sequence: u16,
+    pub(super) length: u32,
+    pub(super) name_len: u16,
+    pub(super) _pad1: [u8; 22],
+}
+
+#[repr(C)]
+pub(super) struct xcb_get_selection_owner_reply_t {
+    pub(super) response_type: u8,
+    pub(super) _pad0: u8,
+    pub(super) sequence: u16,
+    pub(super) length: u32,
+    pub(super) owner: xcb_window_t,
+}
+
 #[repr(C)]
 pub(crate) union ClientData {
     pub(crate) data8: [u8; 20],
@@ -428,16 +860,23 @@ mod input {
     //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_RAW_KEY_RELEASE: u32 = 16384;
     //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_RAW_BUTTON_PRESS: u32 = 32768;
     //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_RAW_BUTTON_RELEASE: u32 = 65536;
-    //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_RAW_MOTION: u32 = 131072;
-    //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_TOUCH_BEGIN: u32 = 262144;
-    //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_TOUCH_UPDATE: u32 = 524288;
-    //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_TOUCH_END: u32 = 1048576;
+    pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_RAW_MOTION: u32 = 131072;
+    pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_TOUCH_BEGIN: u32 = 262144;
+    pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_TOUCH_UPDATE: u32 = 524288;
+    pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_TOUCH_END: u32 = 1048576;
     //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_TOUCH_OWNERSHIP: u32 = 2097152;
     //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_RAW_TOUCH_BEGIN: u32 = 4194304;
     //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_RAW_TOUCH_UPDATE: u32 = 8388608;
     //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_RAW_TOUCH_END: u32 = 16777216;
     //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_BARRIER_HIT: u32 = 33554432;
     //pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_BARRIER_LEAVE: u32 = 67108864;
+    pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_GESTURE_PINCH_BEGIN: u32 = 134217728;
+    pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_GESTURE_PINCH_UPDATE: u32 = 268435456;
+    pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_GESTURE_PINCH_END: u32 = 536870912;
+    pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_GESTURE_SWIPE_BEGIN: u32 = 1073741824;
+    pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_GESTURE_SWIPE_UPDATE: u32 = 2147483648;
+    // This one doesn't fit in 32 bits, so it goes in the second word of the mask
+    pub(in super::super) const XCB_INPUT_XI_EVENT_MASK_GESTURE_SWIPE_END_HIGH: u32 = 1;
 
     //pub(in super::super) const XCB_INPUT_DEVICE_ALL: u16 = 0;
     pub(in super::super) const XCB_INPUT_DEVICE_ALL_MASTER: u16 = 1;
@@ -451,11 +890,39 @@ mod input {
     pub(in super::super) const XCB_INPUT_LEAVE: u16 = 8;
     pub(in super::super) const XCB_INPUT_FOCUS_IN: u16 = 9;
     pub(in super::super) const XCB_INPUT_FOCUS_OUT: u16 = 10;
+    pub(in super::super) const XCB_INPUT_RAW_MOTION: u16 = 17;
+    pub(in super::super) const XCB_INPUT_TOUCH_BEGIN: u16 = 18;
+    pub(in super::super) const XCB_INPUT_TOUCH_UPDATE: u16 = 19;
+    pub(in super::super) const XCB_INPUT_TOUCH_END: u16 = 20;
+    pub(in super::super) const XCB_INPUT_GESTURE_PINCH_BEGIN: u16 = 27;
+    pub(in super::super) const XCB_INPUT_GESTURE_PINCH_UPDATE: u16 = 28;
+    pub(in super::super) const XCB_INPUT_GESTURE_PINCH_END: u16 = 29;
+    pub(in super::super) const XCB_INPUT_GESTURE_SWIPE_BEGIN: u16 = 30;
+    pub(in super::super) const XCB_INPUT_GESTURE_SWIPE_UPDATE: u16 = 31;
+    pub(in super::super) const XCB_INPUT_GESTURE_SWIPE_END: u16 = 32;
+    pub(in super::super) const XCB_INPUT_GESTURE_PINCH_EVENT_FLAGS_GESTURE_PINCH_CANCELLED: u32 = 1;
+    pub(in super::super) const XCB_INPUT_GESTURE_SWIPE_EVENT_FLAGS_GESTURE_SWIPE_CANCELLED: u32 = 1;
 
     pub(in super::super) const XCB_INPUT_KEY_EVENT_FLAGS_KEY_REPEAT: u32 = 65536;
+    pub(in super::super) const XCB_INPUT_POINTER_EVENT_FLAGS_POINTER_EMULATED: u32 = 65536;
+
+    //pub(in super::super) const XCB_INPUT_DEVICE_CLASS_TYPE_KEY: u16 = 0;
+    //pub(in super::super) const XCB_INPUT_DEVICE_CLASS_TYPE_BUTTON: u16 = 1;
+    pub(in super::super) const XCB_INPUT_DEVICE_CLASS_TYPE_VALUATOR: u16 = 2;
+    pub(in super::super) const XCB_INPUT_DEVICE_CLASS_TYPE_SCROLL: u16 = 3;
+    //pub(in super::super) const XCB_INPUT_DEVICE_CLASS_TYPE_TOUCH: u16 = 8;
+
+    //pub(in super::super) const XCB_INPUT_SCROLL_TYPE_VERTICAL: u16 = 1;
+    pub(in super::super) const XCB_INPUT_SCROLL_TYPE_HORIZONTAL: u16 = 2;
 
     pub(in super::super) type xcb_input_fp1616_t = i32;
 
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_fp3232_t {
+        pub(in super::super) integral: i32,
+        pub(in super::super) frac: u32,
+    }
+
     #[repr(C)]
     pub(in super::super) struct xcb_input_event_mask_t {
         pub(in super::super) deviceid: xcb_input_device_id_t,
@@ -489,7 +956,7 @@ mod input {
         pub(in super::super) group: xcb_input_group_info_t,
     }
     pub(in super::super) type xcb_input_leave_event_t = xcb_input_enter_event_t;
-    //pub(in super::super) type xcb_input_focus_in_event_t = xcb_input_enter_event_t;
+    pub(in super::super) type xcb_input_focus_in_event_t = xcb_input_enter_event_t;
     //pub(in super::super) type xcb_input_focus_out_event_t = xcb_input_enter_event_t;
 
     #[repr(C)]
@@ -522,6 +989,188 @@ mod input {
     pub(in super::super) type xcb_input_button_press_event_t = xcb_input_key_press_event_t;
     //pub(in super::super) type xcb_input_button_release_event_t = xcb_input_key_press_event_t;
     pub(in super::super) type xcb_input_motion_event_t = xcb_input_key_press_event_t;
+    pub(in super::super) type xcb_input_touch_begin_event_t = xcb_input_key_press_event_t;
+
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_gesture_pinch_begin_event_t {
+        pub(in super::super) response_type: u8,
+        pub(in super::super) extension: u8,
+        pub(in super::super) sequence: u16,
+        pub(in super::super) length: u32,
+        pub(in super::super) event_type: u16,
+        pub(in super::super) deviceid: xcb_input_device_id_t,
+        pub(in super::super) time: xcb_timestamp_t,
+        pub(in super::super) detail: u32,
+        pub(in super::super) root: xcb_window_t,
+        pub(in super::super) event: xcb_window_t,
+        pub(in super::super) child: xcb_window_t,
+        pub(in super::super) full_sequence: u32,
+        pub(in super::super) root_x: xcb_input_fp1616_t,
+        pub(in super::super) root_y: xcb_input_fp1616_t,
+        pub(in super::super) event_x: xcb_input_fp1616_t,
+        pub(in super::super) event_y: xcb_input_fp1616_t,
+        pub(in super::super) delta_x: xcb_input_fp1616_t,
+        pub(in super::super) delta_y: xcb_input_fp1616_t,
+        pub(in super::super) delta_unaccel_x: xcb_input_fp1616_t,
+        pub(in super::super) delta_unaccel_y: xcb_input_fp1616_t,
+        pub(in super::super) scale: xcb_input_fp1616_t,
+        pub(in super::super) delta_angle: xcb_input_fp1616_t,
+        pub(in super::super) sourceid: xcb_input_device_id_t,
+        pub(in super::super) _pad0: [u8; 2],
+        pub(in super::super) mods: xcb_input_modifier_info_t,
+        pub(in super::super) group: xcb_input_group_info_t,
+        pub(in super::super) flags: u32,
+    }
+
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_gesture_swipe_begin_event_t {
+        pub(in super::super) response_type: u8,
+        pub(in super::super) extension: u8,
+        pub(in super::super) sequence: u16,
+        pub(in super::super) length: u32,
+        pub(in super::super) event_type: u16,
+        pub(in super::super) deviceid: xcb_input_device_id_t,
+        pub(in super::super) time: xcb_timestamp_t,
+        pub(in super::super) detail: u32,
+        pub(in super::super) root: xcb_window_t,
+        pub(in super::super) event: xcb_window_t,
+        pub(in super::super) child: xcb_window_t,
+        pub(in super::super) full_sequence: u32,
+        pub(in super::super) root_x: xcb_input_fp1616_t,
+        pub(in super::super) root_y: xcb_input_fp1616_t,
+        pub(in super::super) event_x: xcb_input_fp1616_t,
+        pub(in super::super) event_y: xcb_input_fp1616_t,
+        pub(in super::super) delta_x: xcb_input_fp1616_t,
+        pub(in super::super) delta_y: xcb_input_fp1616_t,
+        pub(in super::super) delta_unaccel_x: xcb_input_fp1616_t,
+        pub(in super::super) delta_unaccel_y: xcb_input_fp1616_t,
+        pub(in super::super) sourceid: xcb_input_device_id_t,
+        pub(in super::super) _pad0: [u8; 2],
+        pub(in super::super) mods: xcb_input_modifier_info_t,
+        pub(in super::super) group: xcb_input_group_info_t,
+        pub(in super::super) flags: u32,
+    }
+
+    // Followed by `valuators_len` words of valuator mask, then one `xcb_input_fp3232_t` per bit set in that mask for
+    // the processed axis values, then the same again for the raw (unaccelerated) axis values
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_raw_button_press_event_t {
+        pub(in super::super) response_type: u8,
+        pub(in super::super) extension: u8,
+        pub(in super::super) sequence: u16,
+        pub(in super::super) length: u32,
+        pub(in super::super) event_type: u16,
+        pub(in super::super) deviceid: xcb_input_device_id_t,
+        pub(in super::super) time: xcb_timestamp_t,
+        pub(in super::super) detail: u32,
+        pub(in super::super) sourceid: xcb_input_device_id_t,
+        pub(in super::super) valuators_len: u16,
+        pub(in super::super) flags: u32,
+        pub(in super::super) _pad0: [u8; 4],
+        pub(in super::super) full_sequence: u32,
+    }
+    pub(in super::super) type xcb_input_raw_motion_event_t = xcb_input_raw_button_press_event_t;
+
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_xi_query_version_reply_t {
+        pub(in super::super) response_type: u8,
+        pub(in super::super) _pad0: u8,
+        pub(in super::super) sequence: u16,
+        pub(in super::super) length: u32,
+        pub(in super::super) major_version: u16,
+        pub(in super::super) minor_version: u16,
+        pub(in super::super) _pad1: [u8; 20],
+    }
+
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_xi_get_client_pointer_reply_t {
+        pub(in super::super) response_type: u8,
+        pub(in super::super) _pad0: u8,
+        pub(in super::super) sequence: u16,
+        pub(in super::super) length: u32,
+        pub(in super::super) set: u8,
+        pub(in super::super) _pad1: u8,
+        pub(in super::super) deviceid: xcb_input_device_id_t,
+        pub(in super::super) _pad2: [u8; 20],
+    }
+
+    // Followed by `buttons_len` words of button state
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_xi_query_pointer_reply_t {
+        pub(in super::super) response_type: u8,
+        pub(in super::super) _pad0: u8,
+        pub(in super::super) sequence: u16,
+        pub(in super::super) length: u32,
+        pub(in super::super) root: xcb_window_t,
+        pub(in super::super) child: xcb_window_t,
+        pub(in super::super) root_x: xcb_input_fp1616_t,
+        pub(in super::super) root_y: xcb_input_fp1616_t,
+        pub(in super::super) win_x: xcb_input_fp1616_t,
+        pub(in super::super) win_y: xcb_input_fp1616_t,
+        pub(in super::super) same_screen: u8,
+        pub(in super::super) _pad1: u8,
+        pub(in super::super) buttons_len: u16,
+        pub(in super::super) mods: xcb_input_modifier_info_t,
+        pub(in super::super) group: xcb_input_group_info_t,
+    }
+
+    // Followed by `num_infos` variable-length `xcb_input_xi_device_info_t`s
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_xi_query_device_reply_t {
+        pub(in super::super) response_type: u8,
+        pub(in super::super) _pad0: u8,
+        pub(in super::super) sequence: u16,
+        pub(in super::super) length: u32,
+        pub(in super::super) num_infos: u16,
+        pub(in super::super) _pad1: [u8; 22],
+    }
+
+    // Followed by the device name, padded to 4 bytes, then `num_classes` variable-length classes
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_xi_device_info_t {
+        pub(in super::super) deviceid: xcb_input_device_id_t,
+        pub(in super::super) r#type: u16,
+        pub(in super::super) attachment: xcb_input_device_id_t,
+        pub(in super::super) num_classes: u16,
+        pub(in super::super) name_len: u16,
+        pub(in super::super) enabled: u8,
+        pub(in super::super) _pad0: u8,
+    }
+
+    // `len` is the length of the whole class in 4-byte units
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_device_class_t {
+        pub(in super::super) r#type: u16,
+        pub(in super::super) len: u16,
+        pub(in super::super) sourceid: xcb_input_device_id_t,
+    }
+
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_valuator_class_t {
+        pub(in super::super) r#type: u16,
+        pub(in super::super) len: u16,
+        pub(in super::super) sourceid: xcb_input_device_id_t,
+        pub(in super::super) number: u16,
+        pub(in super::super) label: xcb_atom_t,
+        pub(in super::super) min: xcb_input_fp3232_t,
+        pub(in super::super) max: xcb_input_fp3232_t,
+        pub(in super::super) value: xcb_input_fp3232_t,
+        pub(in super::super) resolution: u32,
+        pub(in super::super) mode: u8,
+        pub(in super::super) _pad0: [u8; 3],
+    }
+
+    #[repr(C)]
+    pub(in super::super) struct xcb_input_scroll_class_t {
+        pub(in super::super) r#type: u16,
+        pub(in super::super) len: u16,
+        pub(in super::super) sourceid: xcb_input_device_id_t,
+        pub(in super::super) number: u16,
+        pub(in super::super) scroll_type: u16,
+        pub(in super::super) _pad0: [u8; 2],
+        pub(in super::super) flags: u32,
+        pub(in super::super) increment: xcb_input_fp3232_t,
+    }
 
     #[repr(C)]
     pub(in super::super) struct xcb_input_modifier_info_t {
@@ -540,6 +1189,197 @@ mod input {
     }
 
     pub(in super::super) type KeySym = c_ulong;
+
+    #[repr(C)]
+    pub(in super::super) union XEvent {
+        pub(in super::super) r#type: c_int,
+        pub(in super::super) key: XKeyEvent,
+        pub(in super::super) pad: [libc::c_long; 24],
+    }
+
+    pub(in super::super) const KeyPress: c_int = 2;
+    pub(in super::super) const KeyRelease: c_int = 3;
+
+    // Input methods
+    pub(in super::super) type XIM = *mut c_void;
+    pub(in super::super) type XIC = *mut c_void;
+    pub(in super::super) type XIMStyle = c_ulong;
+    pub(in super::super) type XVaNestedList = *mut c_void;
+
+    pub(in super::super) const XIMPreeditCallbacks: XIMStyle = 0x0002;
+    pub(in super::super) const XIMPreeditNothing: XIMStyle = 0x0008;
+    pub(in super::super) const XIMStatusNothing: XIMStyle = 0x0400;
+
+    pub(in super::super) const XLookupChars: c_int = 2;
+    pub(in super::super) const XLookupBoth: c_int = 4;
+    pub(in super::super) const XBufferOverflow: c_int = -1;
+
+    pub(in super::super) const XIMForwardChar: c_int = 0;
+    pub(in super::super) const XIMBackwardChar: c_int = 1;
+    pub(in super::super) const XIMLineStart: c_int = 8;
+    pub(in super::super) const XIMLineEnd: c_int = 9;
+    pub(in super::super) const XIMAbsolutePosition: c_int = 10;
+
+    pub(in super::super) const XNQueryInputStyle: *const c_char = cstr!("queryInputStyle");
+    pub(in super::super) const XNInputStyle: *const c_char = cstr!("inputStyle");
+    pub(in super::super) const XNClientWindow: *const c_char = cstr!("clientWindow");
+    pub(in super::super) const XNFocusWindow: *const c_char = cstr!("focusWindow");
+    pub(in super::super) const XNPreeditAttributes: *const c_char = cstr!("preeditAttributes");
+    pub(in super::super) const XNPreeditStartCallback: *const c_char = cstr!("preeditStartCallback");
+    pub(in super::super) const XNPreeditDoneCallback: *const c_char = cstr!("preeditDoneCallback");
+    pub(in super::super) const XNPreeditDrawCallback: *const c_char = cstr!("preeditDrawCallback");
+    pub(in super::super) const XNPreeditCaretCallback: *const c_char = cstr!("preeditCaretCallback");
+    pub(in super::super) const XNSpotLocation: *const c_char = cstr!("spotLocation");
+
+    #[repr(C)]
+    pub(in super::super) struct XIMStyles {
+        pub(in super::super) count_styles: u16,
+        pub(in super::super) supported_styles: *mut XIMStyle,
+    }
+
+    #[repr(C)]
+    pub(in super::super) struct XIMCallback {
+        pub(in super::super) client_data: *mut c_void,
+        pub(in super::super) callback: *const c_void,
+    }
+
+    #[repr(C)]
+    pub(in super::super) struct XIMText {
+        pub(in super::super) length: u16,
+        pub(in super::super) feedback: *mut c_ulong,
+        pub(in super::super) encoding_is_wchar: c_int,
+        // Union of `*mut c_char` and `*mut wchar_t`, depending on `encoding_is_wchar`
+        pub(in super::super) string: *mut c_void,
+    }
+
+    #[repr(C)]
+    pub(in super::super) struct XIMPreeditDrawCallbackStruct {
+        pub(in super::super) caret: c_int,
+        pub(in super::super) chg_first: c_int,
+        pub(in super::super) chg_length: c_int,
+        pub(in super::super) text: *mut XIMText,
+    }
+
+    #[repr(C)]
+    pub(in super::super) struct XIMPreeditCaretCallbackStruct {
+        pub(in super::super) position: c_int,
+        pub(in super::super) direction: c_int,
+        pub(in super::super) style: c_int,
+    }
+
+    #[repr(C)]
+    pub(in super::super) struct XPoint {
+        pub(in super::super) x: i16,
+        pub(in super::super) y: i16,
+    }
+
+    pub(in super::super) enum xkb_context {}
+    pub(in super::super) enum xkb_keymap {}
+    pub(in super::super) enum xkb_state {}
+    pub(in super::super) enum xkb_compose_table {}
+    pub(in super::super) enum xkb_compose_state {}
+
+    pub(in super::super) type xkb_keysym_t = u32;
+    pub(in super::super) type xkb_keycode_t = u32;
+    pub(in super::super) type xkb_layout_index_t = u32;
+    pub(in super::super) type xkb_level_index_t = u32;
+    pub(in super::super) type xkb_mod_mask_t = u32;
+
+    pub(in super::super) const XKB_CONTEXT_NO_FLAGS: c_int = 0;
+    pub(in super::super) const XKB_KEYMAP_COMPILE_NO_FLAGS: c_int = 0;
+    pub(in super::super) const XKB_STATE_LAYOUT_EFFECTIVE: c_int = 1 << 7;
+    pub(in super::super) const XKB_X11_MIN_MAJOR_XKB_VERSION: u16 = 1;
+    pub(in super::super) const XKB_X11_MIN_MINOR_XKB_VERSION: u16 = 0;
+    pub(in super::super) const XKB_X11_SETUP_XKB_EXTENSION_NO_FLAGS: c_int = 0;
+    pub(in super::super) const XKB_COMPOSE_COMPILE_NO_FLAGS: c_int = 0;
+    pub(in super::super) const XKB_COMPOSE_STATE_NO_FLAGS: c_int = 0;
+    //pub(in super::super) const XKB_COMPOSE_FEED_IGNORED: c_int = 0;
+    pub(in super::super) const XKB_COMPOSE_FEED_ACCEPTED: c_int = 1;
+    //pub(in super::super) const XKB_COMPOSE_NOTHING: c_int = 0;
+    pub(in super::super) const XKB_COMPOSE_COMPOSING: c_int = 1;
+    pub(in super::super) const XKB_COMPOSE_COMPOSED: c_int = 2;
+    pub(in super::super) const XKB_COMPOSE_CANCELLED: c_int = 3;
+
+    pub(in super::super) const XCB_XKB_ID_USE_CORE_KBD: u16 = 256;
+    pub(in super::super) const XCB_XKB_EVENT_TYPE_NEW_KEYBOARD_NOTIFY: u16 = 1;
+    pub(in super::super) const XCB_XKB_EVENT_TYPE_MAP_NOTIFY: u16 = 2;
+    pub(in super::super) const XCB_XKB_EVENT_TYPE_STATE_NOTIFY: u16 = 4;
+    pub(in super::super) const XCB_XKB_NEW_KEYBOARD_NOTIFY: u8 = 0;
+    pub(in super::super) const XCB_XKB_MAP_NOTIFY: u8 = 1;
+    pub(in super::super) const XCB_XKB_STATE_NOTIFY: u8 = 2;
+
+    // All XKB events share this header, with the specific event type in `xkb_type`
+    #[repr(C)]
+    pub(in super::super) struct xcb_xkb_generic_event_t {
+        pub(in super::super) response_type: u8,
+        pub(in super::super) xkb_type: u8,
+        pub(in super::super) sequence: u16,
+        pub(in super::super) time: xcb_timestamp_t,
+        pub(in super::super) device_id: u8,
+    }
+
+    #[repr(C)]
+    pub(in super::super) struct xcb_xkb_state_notify_event_t {
+        pub(in super::super) response_type: u8,
+        pub(in super::super) xkb_type: u8,
+        pub(in super::super) sequence: u16,
+        pub(in super::super) time: xcb_timestamp_t,
+        pub(in super::super) device_id: u8,
+        pub(in super::super) mods: u8,
+        pub(in super::super) base_mods: u8,
+        pub(in super::super) latched_mods: u8,
+        pub(in super::super) locked_mods: u8,
+        pub(in super::super) group: u8,
+        pub(in super::super) base_group: i16,
+        pub(in super::super) latched_group: i16,
+        pub(in super::super) locked_group: u8,
+        pub(in super::super) compat_state: u8,
+        pub(in super::super) grab_mods: u8,
+        pub(in super::super) compat_grab_mods: u8,
+        pub(in super::super) lookup_mods: u8,
+        pub(in super::super) compat_lookup_mods: u8,
+        pub(in super::super) ptr_btn_state: u16,
+        pub(in super::super) changed: u16,
+        pub(in super::super) keycode: u8,
+        pub(in super::super) event_type: u8,
+        pub(in super::super) request_major: u8,
+        pub(in super::super) request_minor: u8,
+    }
+
+    // Xlib's input method functions are variadic, which `load!` can't describe, so they're looked up separately
+    #[derive(Clone, Copy)]
+    pub(in super::super) struct XimFns {
+        pub(in super::super) XGetIMValues: unsafe extern "C" fn(im: XIM, ...) -> *mut c_char,
+        pub(in super::super) XCreateIC: unsafe extern "C" fn(im: XIM, ...) -> XIC,
+        pub(in super::super) XSetICValues: unsafe extern "C" fn(ic: XIC, ...) -> *mut c_char,
+        pub(in super::super) XVaCreateNestedList: unsafe extern "C" fn(unused: c_int, ...) -> XVaNestedList,
+    }
+
+    impl XimFns {
+        pub(in super::super) unsafe fn load() -> Option<Self> {
+            let handle = dlopen(cstr!("libX11.so.6"));
+            if handle.is_null() {
+                return None
+            }
+            macro_rules! sym {
+                ($name:literal) => {{
+                    let sym = dlsym(handle, cstr!($name));
+                    if sym.is_null() {
+                        return None
+                    }
+                    *(&sym as *const *mut c_void).cast()
+                }};
+            }
+            Some(Self {
+                XGetIMValues: sym!("XGetIMValues"),
+                XCreateIC: sym!("XCreateIC"),
+                XSetICValues: sym!("XSetICValues"),
+                XVaCreateNestedList: sym!("XVaCreateNestedList"),
+            })
+        }
+    }
+
+    #[derive(Clone, Copy)]
     #[repr(C)]
     pub(in super::super) struct XKeyEvent {
         pub(in super::super) r#type: c_int,
@@ -0,0 +1,345 @@
+// X Input Method (XIM) support
+//
+// The main connection has XCB owning its event queue, but Xlib's XIM implementation needs to read events itself
+// (it blocks in `XIfEvent` while waiting for the IM server to reply), so the input method gets its own Xlib connection.
+// Key events are passed to it through `XFilterEvent`, and anything the IM sends back is picked up in `Ime::pump`.
+// Since that's a whole extra display connection, it's only opened once some window first allows IME.
+//
+// XIM also only works in the locale the program has set with setlocale(3), which is left up to the application,
+// as changing it would affect the whole process. Rust programs stay in the "C" locale unless they do.
+
+use super::ffi::*;
+use crate::event::Event;
+
+use std::{collections::HashMap, ffi::CStr, mem::MaybeUninit, ptr};
+
+pub(super) struct Ime {
+    display: *mut Display,
+    im: XIM,
+    fns: XimFns,
+    style: XIMStyle,
+    // Key events which the IM sends back to us are routed to windows through this
+    contexts: HashMap<c_ulong, *mut ImeContext>,
+}
+
+// An input context for a single window. This lives in a Box so that the XIM callbacks can hold a pointer to it,
+// and it's only ever touched while holding the connection lock.
+pub(super) struct ImeContext {
+    display: *mut Display,
+    fns: XimFns,
+    ic: XIC,
+    window: c_ulong,
+    allowed: bool,
+    focused: bool,
+    preedit: Vec<char>,
+    caret: usize,
+    events: Vec<Event>,
+    callbacks: [XIMCallback; 4],
+}
+
+unsafe impl Send for ImeContext {}
+unsafe impl Sync for ImeContext {}
+
+impl Ime {
+    pub(super) unsafe fn new() -> Option<Self> {
+        let fns = XimFns::load()?;
+
+        if XSetLocaleModifiers(cstr!("")).is_null() {
+            return None
+        }
+
+        let display = XOpenDisplay(ptr::null());
+        if display.is_null() {
+            return None
+        }
+        let im = XOpenIM(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+        if im.is_null() {
+            let _ = XCloseDisplay(display);
+            return None
+        }
+
+        // We'd prefer to draw the preedit text ourselves (on-the-spot), otherwise let the IM draw it (root window)
+        let mut styles: *mut XIMStyles = ptr::null_mut();
+        let err = (fns.XGetIMValues)(im, XNQueryInputStyle, &mut styles as *mut *mut XIMStyles, ptr::null_mut::<c_void>());
+        let style = if err.is_null() && !styles.is_null() {
+            let supported = std::slice::from_raw_parts((*styles).supported_styles, (*styles).count_styles.into());
+            let style = [XIMPreeditCallbacks | XIMStatusNothing, XIMPreeditNothing | XIMStatusNothing]
+                .iter()
+                .copied()
+                .find(|style| supported.contains(style));
+            let _ = XFree(styles.cast());
+            style
+        } else {
+            None
+        };
+
+        match style {
+            Some(style) => Some(Self { display, im, fns, style, contexts: HashMap::new() }),
+            None => {
+                let _ = XCloseIM(im);
+                let _ = XCloseDisplay(display);
+                None
+            },
+        }
+    }
+
+    pub(super) unsafe fn create_context(&mut self, window: c_ulong) -> Option<Box<ImeContext>> {
+        let mut context = Box::new(ImeContext {
+            display: self.display,
+            fns: self.fns,
+            ic: ptr::null_mut(),
+            window,
+            allowed: false,
+            focused: false,
+            preedit: Vec::new(),
+            caret: 0,
+            events: Vec::new(),
+            callbacks: [
+                XIMCallback { client_data: ptr::null_mut(), callback: preedit_start as *const c_void },
+                XIMCallback { client_data: ptr::null_mut(), callback: preedit_done as *const c_void },
+                XIMCallback { client_data: ptr::null_mut(), callback: preedit_draw as *const c_void },
+                XIMCallback { client_data: ptr::null_mut(), callback: preedit_caret as *const c_void },
+            ],
+        });
+        let client_data: *mut ImeContext = &mut *context;
+        for callback in context.callbacks.iter_mut() {
+            callback.client_data = client_data.cast();
+        }
+
+        let ic = if self.style & XIMPreeditCallbacks != 0 {
+            let attributes = (self.fns.XVaCreateNestedList)(
+                0,
+                XNPreeditStartCallback, &context.callbacks[0] as *const XIMCallback,
+                XNPreeditDoneCallback, &context.callbacks[1] as *const XIMCallback,
+                XNPreeditDrawCallback, &context.callbacks[2] as *const XIMCallback,
+                XNPreeditCaretCallback, &context.callbacks[3] as *const XIMCallback,
+                ptr::null_mut::<c_void>(),
+            );
+            let ic = (self.fns.XCreateIC)(
+                self.im,
+                XNInputStyle, self.style,
+                XNClientWindow, window,
+                XNFocusWindow, window,
+                XNPreeditAttributes, attributes,
+                ptr::null_mut::<c_void>(),
+            );
+            let _ = XFree(attributes);
+            ic
+        } else {
+            (self.fns.XCreateIC)(
+                self.im,
+                XNInputStyle, self.style,
+                XNClientWindow, window,
+                XNFocusWindow, window,
+                ptr::null_mut::<c_void>(),
+            )
+        };
+        if ic.is_null() {
+            return None
+        }
+        context.ic = ic;
+
+        // Input contexts start out focused, but we only want that once the window is focused and IME is allowed
+        XUnsetICFocus(ic);
+        let _ = XFlush(self.display);
+        let _ = self.contexts.insert(window, client_data);
+        Some(context)
+    }
+
+    pub(super) unsafe fn destroy_context(&mut self, context: &ImeContext) {
+        let _ = self.contexts.remove(&context.window);
+        XDestroyIC(context.ic);
+        let _ = XFlush(self.display);
+    }
+
+    // Processes everything the IM server has sent us, such as preedit updates and committed text.
+    pub(super) unsafe fn pump(&mut self) {
+        while XPending(self.display) > 0 {
+            let mut event = MaybeUninit::<XEvent>::uninit();
+            let _ = XNextEvent(self.display, event.as_mut_ptr());
+            let mut event = event.assume_init();
+            if XFilterEvent(&mut event, 0) != 0 {
+                continue
+            }
+
+            // Keys which the IM didn't want come back to us, and so does committed text in the form of
+            // a fake key press with a keycode of 0
+            if event.r#type == KeyPress {
+                if let Some(&context) = self.contexts.get(&event.key.window) {
+                    let context = &mut *context;
+                    if let Some(text) = lookup_string(context.ic, &mut event.key) {
+                        if event.key.keycode == 0 {
+                            context.events.push(Event::ImeCommit(text));
+                        } else {
+                            context.events.extend(text.chars().map(Event::Input));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Ime {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = XCloseIM(self.im);
+            let _ = XCloseDisplay(self.display);
+        }
+    }
+}
+
+impl ImeContext {
+    // Whether key events should be going through the IM
+    pub(super) fn is_active(&self) -> bool {
+        self.allowed && self.focused
+    }
+
+    pub(super) unsafe fn set_allowed(&mut self, allowed: bool) {
+        self.allowed = allowed;
+        self.update_focus();
+    }
+
+    pub(super) unsafe fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+        self.update_focus();
+    }
+
+    unsafe fn update_focus(&mut self) {
+        if self.is_active() {
+            XSetICFocus(self.ic);
+        } else {
+            // Throw away anything that was half-typed, otherwise it'll still be there when IME is next allowed
+            let discarded = Xutf8ResetIC(self.ic);
+            if !discarded.is_null() {
+                let _ = XFree(discarded.cast());
+            }
+            XUnsetICFocus(self.ic);
+            if !self.preedit.is_empty() {
+                self.preedit.clear();
+                self.caret = 0;
+                self.events.push(Event::ImePreedit { text: String::new(), cursor_range: None });
+            }
+        }
+        let _ = XFlush(self.display);
+    }
+
+    pub(super) unsafe fn set_cursor_area(&mut self, (x, y): (i16, i16), (_width, height): (u16, u16)) {
+        // The spot location is where the baseline of the text starts, so the bottom-left corner is the best fit
+        let spot = XPoint { x, y: y.saturating_add(height as i16) };
+        let attributes = (self.fns.XVaCreateNestedList)(
+            0,
+            XNSpotLocation, &spot as *const XPoint,
+            ptr::null_mut::<c_void>(),
+        );
+        let _ = (self.fns.XSetICValues)(self.ic, XNPreeditAttributes, attributes, ptr::null_mut::<c_void>());
+        let _ = XFree(attributes);
+        let _ = XFlush(self.display);
+    }
+
+    // Passes a key event to the IM. If the IM isn't interested in it, returns the text it typed.
+    pub(super) unsafe fn filter_key(&mut self, event: &XKeyEvent) -> Option<String> {
+        let mut event = XEvent { key: *event };
+        event.key.display = self.display;
+        if XFilterEvent(&mut event, 0) != 0 || event.r#type != KeyPress {
+            None
+        } else {
+            lookup_string(self.ic, &mut event.key)
+        }
+    }
+
+    // Moves any events generated by the IM into the window's event buffer
+    pub(super) fn take_events(&mut self, buffer: &mut Vec<Event>) {
+        buffer.append(&mut self.events);
+    }
+
+    fn push_preedit(&mut self) {
+        let text = self.preedit.iter().collect();
+        let cursor = self.preedit[..self.caret].iter().map(|c| c.len_utf8()).sum();
+        self.events.push(Event::ImePreedit { text, cursor_range: Some((cursor, cursor)) });
+    }
+}
+
+#[allow(non_upper_case_globals)]
+unsafe fn lookup_string(ic: XIC, event: &mut XKeyEvent) -> Option<String> {
+    let mut buffer = vec![0u8; 32];
+    loop {
+        let mut status = 0;
+        let len = Xutf8LookupString(
+            ic,
+            event,
+            buffer.as_mut_ptr().cast(),
+            buffer.len() as c_int,
+            ptr::null_mut(),
+            &mut status,
+        );
+        match status {
+            // Xlib holds onto the text until we ask for it with a big enough buffer
+            XBufferOverflow => buffer.resize(len as usize, 0),
+            XLookupChars | XLookupBoth => {
+                return Some(String::from_utf8_lossy(&buffer[..len as usize]).into_owned())
+            },
+            _ => return None,
+        }
+    }
+}
+
+unsafe extern "C" fn preedit_start(_ic: XIC, client_data: *mut c_void, _call_data: *mut c_void) -> c_int {
+    let context = &mut *(client_data as *mut ImeContext);
+    context.preedit.clear();
+    context.caret = 0;
+    -1 // No length limit
+}
+
+unsafe extern "C" fn preedit_done(_ic: XIC, client_data: *mut c_void, _call_data: *mut c_void) {
+    let context = &mut *(client_data as *mut ImeContext);
+    context.preedit.clear();
+    context.caret = 0;
+    context.events.push(Event::ImePreedit { text: String::new(), cursor_range: None });
+}
+
+unsafe extern "C" fn preedit_draw(_ic: XIC, client_data: *mut c_void, call_data: *mut XIMPreeditDrawCallbackStruct) {
+    let context = &mut *(client_data as *mut ImeContext);
+    let data = &*call_data;
+    let len = context.preedit.len();
+    let first = (data.chg_first.max(0) as usize).min(len);
+    let end = first.saturating_add(data.chg_length.max(0) as usize).min(len);
+
+    // A null text means the changed range was deleted, but a null string only means the styling changed
+    let replacement: Option<Vec<char>> = if data.text.is_null() {
+        Some(Vec::new())
+    } else {
+        let text = &*data.text;
+        if text.string.is_null() {
+            None
+        } else if text.encoding_is_wchar != 0 {
+            let chars = std::slice::from_raw_parts(text.string as *const u32, text.length.into());
+            Some(chars.iter().filter_map(|c| char::from_u32(*c)).collect())
+        } else {
+            // This is in the locale's encoding, which is UTF-8 on anything modern
+            Some(CStr::from_ptr(text.string.cast()).to_string_lossy().chars().collect())
+        }
+    };
+    if let Some(replacement) = replacement {
+        let _ = context.preedit.splice(first..end, replacement);
+    }
+    context.caret = (data.caret.max(0) as usize).min(context.preedit.len());
+    context.push_preedit();
+}
+
+#[allow(non_upper_case_globals)]
+unsafe extern "C" fn preedit_caret(_ic: XIC, client_data: *mut c_void, call_data: *mut XIMPreeditCaretCallbackStruct) {
+    let context = &mut *(client_data as *mut ImeContext);
+    let data = &mut *call_data;
+    let len = context.preedit.len();
+    context.caret = match data.direction {
+        XIMForwardChar => (context.caret + 1).min(len),
+        XIMBackwardChar => context.caret.saturating_sub(1),
+        XIMLineStart => 0,
+        XIMLineEnd => len,
+        XIMAbsolutePosition => (data.position.max(0) as usize).min(len),
+        _ => context.caret,
+    };
+    data.position = context.caret as c_int;
+    context.push_preedit();
+}
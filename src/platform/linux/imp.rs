@@ -1,17 +1,40 @@
 // TODO: I suppose we'll need some method of deciding at runtime whether to use x11 or wayland? This is just x11
-use crate::{error::Error, event::Event, util::sync::{mutex_lock, Mutex}, connection, window};
-use super::ffi::*;
+use crate::{
+    clipboard::Selection,
+    connection,
+    dnd::{DragAction, DragData},
+    error::Error,
+    event::Event,
+    util::sync::{mutex_lock, Mutex},
+    window::{self, CursorGrab},
+};
+#[cfg(feature = "input")]
+use crate::{
+    input::{KeyboardLayouts, Modifiers, MouseButton, PenTool, ScanCode, ScrollUnit, TouchPhase},
+    window::CursorMode,
+};
+use super::{clipboard::Clipboard, dnd::{self, DndAtoms, DragSource, DropTarget}, ffi::*, x11};
+#[cfg(feature = "input")]
+use super::{ime::{Ime, ImeContext}, keyboard::{Compose, ComposeResult, Keyboard}};
 
 use std::{collections::HashMap, sync::Arc};
 
 /// The initial capacity for any Vec<Event>
-/// Event is around 8 bytes in size, so it's fairly costless for this to be a large starting capacity.
+/// Event is only a few words in size, so it's fairly costless for this to be a large starting capacity.
 const QUEUE_SIZE: usize = 256;
 
 pub(crate) struct Connection {
     details: ConnectionDetails,
     event_buffer: HashMap<xcb_window_t, Vec<*mut xcb_generic_event_t>>,
     hostname: Option<Vec<c_char>>,
+    clipboard: Clipboard,
+    // Only opened once a window first allows IME, and then `Some(None)` if there's no input method available
+    #[cfg(feature = "input")]
+    ime: Option<Option<Ime>>,
+    // `None` if xkbcommon isn't installed or couldn't give us a keymap, in which case keys are reported by their
+    // position alone
+    #[cfg(feature = "input")]
+    keyboard: Option<Keyboard>,
 }
 
 // Proxy struct for passing Connection details around without the allocated parts
@@ -22,7 +45,13 @@ struct ConnectionDetails {
     screen: *mut xcb_screen_t,
     screenid: u32,
     atoms: Atoms,
+    dnd_atoms: DndAtoms,
     extensions: Extensions,
+    #[cfg(feature = "input")]
+    blank_cursor: xcb_cursor_t,
+    // Raw input events aren't reported relative to any window, so they get sent to whichever window has focus
+    #[cfg(feature = "input")]
+    input_focus: xcb_window_t,
 }
 
 #[derive(Clone, Copy)]
@@ -39,12 +68,22 @@ struct Atoms {
     _net_wm_state_maximized_vert: xcb_atom_t,
     _net_wm_state_hidden: xcb_atom_t,
     _motif_wm_hints: xcb_atom_t,
+    _net_wm_window_opacity: xcb_atom_t,
+    #[cfg(feature = "input")]
+    abs_pressure: xcb_atom_t,
+    #[cfg(feature = "input")]
+    abs_tilt_x: xcb_atom_t,
+    #[cfg(feature = "input")]
+    abs_tilt_y: xcb_atom_t,
 }
 
 #[derive(Clone, Copy)]
 struct Extensions {
     #[cfg(feature = "input")]
     xinput: u8,
+    // The XI2 version the server agreed to speak, as (major, minor)
+    #[cfg(feature = "input")]
+    xinput_version: (u16, u16),
 }
 
 impl Connection {
@@ -74,6 +113,8 @@ impl Connection {
             #[cfg(feature = "input")]
             let xi_opcode;
             #[cfg(feature = "input")]
+            let xi_version;
+            #[cfg(feature = "input")]
             {
                 // xcb_query_extension cannot generate errors, so we don't check
                 let xi_name = "XInputExtension";
@@ -92,7 +133,34 @@ impl Connection {
                 free(xi.cast());
 
                 libxcb_xinput::load()?;
-                libxkbcommon::load()?;
+
+                // We have to tell the server which version we speak before it will describe scroll valuators (2.1),
+                // or send us newer events like touches (2.2) and gestures (2.4)
+                let version = xcb_input_xi_query_version_reply(
+                    connection,
+                    xcb_input_xi_query_version(connection, 2, 4),
+                    std::ptr::null_mut(),
+                );
+                if version.is_null() {
+                    return Err(Error::Unsupported)
+                }
+                xi_version = ((*version).major_version, (*version).minor_version);
+                free(version.cast());
+
+                // Raw events can only be selected on the root window
+                let mut mask = XiMask {
+                    head: xcb_input_event_mask_t {
+                        deviceid: XCB_INPUT_DEVICE_ALL_MASTER,
+                        mask_len: 1,
+                    },
+                    body: [XCB_INPUT_XI_EVENT_MASK_RAW_MOTION, 0],
+                };
+                xcb_discard_reply(connection, xcb_input_xi_select_events_checked(
+                    connection,
+                    (*screen).root,
+                    1,
+                    (&mut mask.head) as _,
+                ));
             }
 
             // Try to get machine's hostname
@@ -128,13 +196,25 @@ impl Connection {
                     screen,
                     screenid: screen_num as _,
                     atoms,
+                    dnd_atoms: DndAtoms::new(connection)?,
                     extensions: Extensions {
                         #[cfg(feature = "input")]
                         xinput: xi_opcode,
+                        #[cfg(feature = "input")]
+                        xinput_version: xi_version,
                     },
+                    #[cfg(feature = "input")]
+                    blank_cursor: create_blank_cursor(connection, (*screen).root),
+                    #[cfg(feature = "input")]
+                    input_focus: 0,
                 },
                 event_buffer: HashMap::new(),
                 hostname,
+                clipboard: Clipboard::new(connection, (*screen).root)?,
+                #[cfg(feature = "input")]
+                ime: None,
+                #[cfg(feature = "input")]
+                keyboard: Keyboard::new(connection),
             })
         }
     }
@@ -147,6 +227,106 @@ impl Connection {
         self.details.display
     }
 
+    #[cfg(feature = "input")]
+    pub(crate) fn keyboard_layouts(&mut self) -> KeyboardLayouts {
+        // XKB events are usually read when a window polls, but there might not be one doing that
+        unsafe { self.drain_events() };
+        self.keyboard.as_ref().map(|keyboard| unsafe { keyboard.layouts() }).unwrap_or_default()
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn set_keyboard_layout(&self, idx: usize) {
+        if let Some(keyboard) = &self.keyboard {
+            unsafe { keyboard.set_layout(idx) }
+        }
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn key_label(&mut self, key: Key) -> String {
+        unsafe { self.drain_events() };
+        match &self.keyboard {
+            Some(keyboard) => unsafe { keyboard.key_label(key) },
+            None => key.to_string(),
+        }
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn key_for_char(&mut self, ch: char) -> Option<(Key, Modifiers)> {
+        unsafe { self.drain_events() };
+        self.keyboard.as_ref().and_then(|keyboard| unsafe { keyboard.key_for_char(ch) })
+    }
+
+    pub(crate) fn clipboard_get_text(&mut self, selection: Selection) -> Option<String> {
+        let (clipboard, mut route) = self.clipboard_parts();
+        unsafe { clipboard.get_text(selection, &mut route) }
+    }
+
+    pub(crate) fn clipboard_set_text(&mut self, selection: Selection, text: &str) -> Result<(), Error> {
+        let (clipboard, mut route) = self.clipboard_parts();
+        unsafe { clipboard.set_text(selection, text, &mut route) }
+    }
+
+    pub(crate) fn clipboard_targets(&mut self, selection: Selection) -> Vec<String> {
+        let (clipboard, mut route) = self.clipboard_parts();
+        unsafe { clipboard.targets(selection, &mut route) }
+    }
+
+    pub(crate) fn clipboard_get(&mut self, selection: Selection, mime_type: &str) -> Option<Vec<u8>> {
+        let (clipboard, mut route) = self.clipboard_parts();
+        unsafe { clipboard.get_data(selection, mime_type, &mut route) }
+    }
+
+    pub(crate) fn clipboard_set(&mut self, selection: Selection, formats: &[(&str, &[u8])]) -> Result<(), Error> {
+        let (clipboard, mut route) = self.clipboard_parts();
+        unsafe { clipboard.set_data(selection, formats, &mut route) }
+    }
+
+    pub(crate) fn persist_clipboard(&mut self) -> Result<(), Error> {
+        let (clipboard, mut route) = self.clipboard_parts();
+        unsafe { clipboard.persist(&mut route) }
+    }
+
+    // Splits off the clipboard, along with a function for queueing any events it reads for our other windows while
+    // it's waiting for a reply from another client
+    fn clipboard_parts(&mut self) -> (&mut Clipboard, impl FnMut(*mut xcb_generic_event_t) + '_) {
+        let Connection {
+            details,
+            event_buffer,
+            clipboard,
+            #[cfg(feature = "input")]
+            keyboard,
+            ..
+        } = self;
+        let route = move |event| unsafe {
+            #[cfg(feature = "input")]
+            if let Some(keyboard) = keyboard {
+                keyboard.process_event(event);
+            }
+            let window = get_event_window(event, details);
+            queue_event(event, window, event_buffer);
+        };
+        (clipboard, route)
+    }
+
+    // Reads in any events that have arrived, keeping the keyboard and clipboard up to date, and queues the rest for
+    // the windows they're destined for
+    unsafe fn drain_events(&mut self) {
+        let c = self.details.connection;
+        // `xcb_poll_for_event` reads from the connection, then the queued version gets the rest of what it read
+        let mut event = xcb_poll_for_event(c);
+        while !event.is_null() {
+            #[cfg(feature = "input")]
+            if let Some(keyboard) = &mut self.keyboard {
+                keyboard.process_event(event);
+            }
+            if !self.clipboard.process_event(event) {
+                let window = get_event_window(event, &mut self.details);
+                queue_event(event, window, &mut self.event_buffer);
+            }
+            event = xcb_poll_for_queued_event(c);
+        }
+    }
+
     // Helper wrapper for `xcb_connection_has_error` for use with `?`. Assumes pointer is valid.
     unsafe fn check(c: *mut xcb_connection_t) -> Result<(), Error> {
         let err = xcb_connection_has_error(c);
@@ -162,6 +342,8 @@ impl Connection {
 impl Drop for Connection {
     fn drop(&mut self) {
         let _ = self.details.extensions;
+        // Anything we've copied would be lost once we're disconnected, so try to leave it with the clipboard manager
+        let _ = self.persist_clipboard();
         unsafe {
             let _ = xcb_flush(self.details.connection);
             let _ = XCloseDisplay(self.details.display);
@@ -173,7 +355,7 @@ unsafe impl Send for Connection {}
 
 impl Atoms {
     unsafe fn new(connection: *mut xcb_connection_t) -> Result<Self, Error> {
-        const N_ATOMS: usize = 12;
+        const N_ATOMS: usize = 16;
         let mut atom_replies = [0 as c_uint; N_ATOMS];
         let mut atoms = [0 as xcb_atom_t; N_ATOMS];
         macro_rules! atom {
@@ -193,6 +375,10 @@ impl Atoms {
         atom!(9, "_NET_WM_STATE_MAXIMIZED_VERT");
         atom!(10, "_NET_WM_STATE_HIDDEN");
         atom!(11, "_MOTIF_WM_HINTS");
+        atom!(12, "Abs Pressure");
+        atom!(13, "Abs Tilt X");
+        atom!(14, "Abs Tilt Y");
+        atom!(15, "_NET_WM_WINDOW_OPACITY");
         for (r, seq) in atoms.iter_mut().zip(atom_replies.into_iter()) {
             let mut err: *mut xcb_generic_error_t = std::ptr::null_mut();
             let reply = xcb_intern_atom_reply(connection, seq, &mut err);
@@ -219,6 +405,13 @@ impl Atoms {
             _net_wm_state_maximized_vert: atoms[9],
             _net_wm_state_hidden: atoms[10],
             _motif_wm_hints: atoms[11],
+            #[cfg(feature = "input")]
+            abs_pressure: atoms[12],
+            #[cfg(feature = "input")]
+            abs_tilt_x: atoms[13],
+            #[cfg(feature = "input")]
+            abs_tilt_y: atoms[14],
+            _net_wm_window_opacity: atoms[15],
         })
     }
 }
@@ -228,25 +421,109 @@ pub(crate) struct Window {
     details: WindowDetails,
 }
 
+// The position and size of the area the input method's candidate window should avoid covering
+#[cfg(feature = "input")]
+type ImeArea = ((i16, i16), (u16, u16));
+
 // Proxy struct that pretty much only exists to get around the fact that we're using Rust
 pub(crate) struct WindowDetails {
     handle: xcb_window_t,
     style: Arc<Mutex<crate::window::Style>>,
+    pointer: Mutex<PointerState>,
     event_buffer: Vec<Event>,
+    focused: bool,
+    #[cfg(feature = "input")]
+    modifiers: Modifiers,
     parent: xcb_window_t,
+    #[cfg(feature = "input")]
+    scroll: Option<ScrollDevice>,
+    #[cfg(feature = "input")]
+    touches: Touches,
+    // The total scale of the current pinch gesture as of its last event
+    #[cfg(feature = "input")]
+    pinch_scale: f64,
+    // Tablet tools which have moved the pointer, by source device ID, or `None` for devices which aren't pens
+    #[cfg(feature = "input")]
+    pens: HashMap<xcb_input_device_id_t, Option<PenDevice>>,
+    // The pen we last saw events from, which we assume is still in proximity until something else happens
+    #[cfg(feature = "input")]
+    pen_proximity: Option<(xcb_input_device_id_t, PenTool)>,
+    // Created the first time IME is allowed on the window
+    #[cfg(feature = "input")]
+    ime: Mutex<Option<Box<ImeContext>>>,
+    // The last area passed to `set_ime_cursor_area`, in case that happened before there was an input context
+    #[cfg(feature = "input")]
+    ime_cursor_area: Mutex<Option<ImeArea>>,
+    // `None` if there's no compose table for the user's locale
+    #[cfg(feature = "input")]
+    compose: Option<Compose>,
+    // The keyboard's `layout_serial` as of the last time we reported a layout change
+    #[cfg(feature = "input")]
+    layout_serial: u32,
+    // The clipboard's serial for each selection as of the last time we reported a change to it
+    clipboard_serials: [u32; 2],
+    // `None` unless the window accepts drops
+    drop_target: Option<DropTarget>,
+    // The drag out of this window, if there is one
+    drag_source: Mutex<Option<DragSource>>,
+    // As of the last time we set it or saw it change, from 0.0 (invisible) to 1.0 (opaque)
+    opacity: Mutex<f32>,
     position: (i16, i16),
     size: (u16, u16),
     state_maximised: (bool, bool), // horz vert
     state_minimised: bool,
 }
 
+// How the pointer should be restricted while the window is focused, see `update_pointer_grab`
+struct PointerState {
+    grab: CursorGrab,
+    #[cfg(feature = "input")]
+    mode: CursorMode,
+    // Whether we're currently holding an active pointer grab on behalf of this window
+    grabbed: bool,
+}
+
+// The scroll valuators of the device currently moving the pointer, see `motion_scroll`
+#[cfg(feature = "input")]
+struct ScrollDevice {
+    deviceid: xcb_input_device_id_t,
+    sourceid: xcb_input_device_id_t,
+    valuators: Vec<ScrollValuator>,
+}
+
+#[cfg(feature = "input")]
+struct ScrollValuator {
+    number: u16,
+    horizontal: bool,
+    increment: f64,
+    // The server reports the accumulated scroll position rather than a delta, so we track the last one we saw
+    value: f64,
+}
+
+// A pen or eraser on a drawing tablet, see `pen_event`
+#[cfg(feature = "input")]
+struct PenDevice {
+    tool: PenTool,
+    pressure: PenValuator,
+    tilt: [Option<PenValuator>; 2],
+    buttons: u8,
+}
+
+// Events only include valuators which have changed, so we keep the last value of each
+#[cfg(feature = "input")]
+struct PenValuator {
+    number: u16,
+    min: f64,
+    max: f64,
+    value: f64,
+}
+
 impl Window {
     pub(crate) fn new(builder: window::Builder) -> Result<Self, Error> {
         unsafe {
             let mut connection_mtx = mutex_lock(&builder.connection.0);
             let connection: &mut Connection = &mut *connection_mtx;
             let c = connection.details.connection;
-            let hostname = connection.hostname.as_ref();
 
             // TODO: copy these from the builder when they're in there
             let (x, y) = builder.position.unwrap_or((0, 0));
@@ -261,23 +538,7 @@ impl Window {
             }
 
             // Clear the event queue, in case any events remain in it intended for a previous object with this xid we just claimed
-            let event = xcb_poll_for_event(c);
-            if !event.is_null() {
-                if let Some(window) = get_event_window(event, &connection.details) {
-                    if let Some(queue) = connection.event_buffer.get_mut(&window) {
-                        queue.push(event);
-                    }
-                }
-            }
-            loop {
-                let event = xcb_poll_for_queued_event(c);
-                if event.is_null() { break }
-                if let Some(window) = get_event_window(event, &connection.details) {
-                    if let Some(queue) = connection.event_buffer.get_mut(&window) {
-                        queue.push(event);
-                    }
-                }
-            }
+            connection.drain_events();
 
             // Create the new X window
             const REGULAR_MASK: u32 = XCB_EVENT_MASK_STRUCTURE_NOTIFY | XCB_EVENT_MASK_PROPERTY_CHANGE;
@@ -288,18 +549,29 @@ impl Window {
             const EVENT_MASK: u32 = XCB_EVENT_MASK_FOCUS_CHANGE | REGULAR_MASK;
             const VALUE_MASK: u32 = XCB_CW_EVENT_MASK;
 
-            let cmap = builder.visual.map(|vi| {
+            // A transparent window needs a visual with an alpha channel, unless one was already picked by hand
+            let (depth, visual) = match (builder.transparent, builder.visual) {
+                (true, None) => match argb_visual(connection.details.screen) {
+                    Some(visual) => (Some(32), Some(visual)),
+                    None => return Err(Error::Unsupported),
+                },
+                _ => (builder.depth, builder.visual),
+            };
+
+            let cmap = visual.map(|vi| {
                 let cmap = xcb_generate_id(c);
                 _ = xcb_create_colormap(c, 0, cmap, (*connection.details.screen).root, vi);
                 _ = xcb_flush(c);
                 cmap
             });
-            let value_mask = if cmap.is_some() { VALUE_MASK|XCB_CW_COLORMAP } else { VALUE_MASK };
-            let value_list = if let Some(c) = cmap { (&[EVENT_MASK, c]).to_vec() } else { (&[EVENT_MASK]).to_vec() };
+            // The border pixel defaults to CopyFromParent, which is a BadMatch if our depth isn't the root's,
+            // so it has to be given along with the colormap even though the border is never drawn
+            let value_mask = if cmap.is_some() { XCB_CW_BORDER_PIXEL|VALUE_MASK|XCB_CW_COLORMAP } else { VALUE_MASK };
+            let value_list = if let Some(c) = cmap { (&[0, EVENT_MASK, c]).to_vec() } else { (&[EVENT_MASK]).to_vec() };
 
             let create_error = xcb_request_check(c, xcb_create_window_checked(
                 c,
-                builder.depth.unwrap_or(XCB_COPY_FROM_PARENT as _),
+                depth.unwrap_or(XCB_COPY_FROM_PARENT as _),
                 xid,
                 (*connection.details.screen).root,
                 x,
@@ -308,14 +580,14 @@ impl Window {
                 height,
                 0,
                 XCB_WINDOW_CLASS_INPUT_OUTPUT,
-                builder.visual.unwrap_or(XCB_COPY_FROM_PARENT.into()),
+                visual.unwrap_or(XCB_COPY_FROM_PARENT.into()),
                 value_mask,
                 &value_list[0],
             ));
             if !create_error.is_null() {
                 // Reasons CreateWindow may fail are:
                 // Alloc - maps to Error::SystemResources
-                // Colormap - we don't currently pass a colormap
+                // Colormap - we only pass a colormap we just created for this visual
                 // Cursor - we do not pass a Cursor
                 // IDChoice - we got our ID straight from xcb_generate_id and didn't use it for anything else
                 // Match - bad configuration of user params, so maps to Error::Invalid
@@ -335,21 +607,32 @@ impl Window {
             #[cfg(feature = "input")]
             {
                 // xcb_input_xi_select_events cannot generate errors so we use _checked and discard it
-                #[repr(C)]
-                struct XiMask {
-                    head: xcb_input_event_mask_t,
-                    body: u32,
-                }
                 let mut mask = XiMask {
                     head: xcb_input_event_mask_t {
                         deviceid: XCB_INPUT_DEVICE_ALL_MASTER,
-                        mask_len: 1,
+                        mask_len: 2,
                     },
-                    body: XCB_INPUT_XI_EVENT_MASK_KEY_PRESS | XCB_INPUT_XI_EVENT_MASK_KEY_RELEASE
+                    body: [XCB_INPUT_XI_EVENT_MASK_KEY_PRESS | XCB_INPUT_XI_EVENT_MASK_KEY_RELEASE
                         | XCB_INPUT_XI_EVENT_MASK_BUTTON_PRESS | XCB_INPUT_XI_EVENT_MASK_BUTTON_RELEASE
                         | XCB_INPUT_XI_EVENT_MASK_MOTION | XCB_INPUT_XI_EVENT_MASK_ENTER | XCB_INPUT_XI_EVENT_MASK_LEAVE
-                        | XCB_INPUT_XI_EVENT_MASK_FOCUS_IN | XCB_INPUT_XI_EVENT_MASK_FOCUS_OUT,
+                        | XCB_INPUT_XI_EVENT_MASK_FOCUS_IN | XCB_INPUT_XI_EVENT_MASK_FOCUS_OUT, 0],
                 };
+                // Touch events are only understood by servers supporting XI 2.2, and gestures by XI 2.4.
+                // Selecting them on an older server would make the whole request fail.
+                let version = connection.details.extensions.xinput_version;
+                if version >= (2, 2) {
+                    mask.body[0] |= XCB_INPUT_XI_EVENT_MASK_TOUCH_BEGIN
+                        | XCB_INPUT_XI_EVENT_MASK_TOUCH_UPDATE
+                        | XCB_INPUT_XI_EVENT_MASK_TOUCH_END;
+                }
+                if version >= (2, 4) {
+                    mask.body[0] |= XCB_INPUT_XI_EVENT_MASK_GESTURE_PINCH_BEGIN
+                        | XCB_INPUT_XI_EVENT_MASK_GESTURE_PINCH_UPDATE
+                        | XCB_INPUT_XI_EVENT_MASK_GESTURE_PINCH_END
+                        | XCB_INPUT_XI_EVENT_MASK_GESTURE_SWIPE_BEGIN
+                        | XCB_INPUT_XI_EVENT_MASK_GESTURE_SWIPE_UPDATE;
+                    mask.body[1] |= XCB_INPUT_XI_EVENT_MASK_GESTURE_SWIPE_END_HIGH;
+                }
                 xcb_discard_reply(c, xcb_input_xi_select_events_checked(c, xid, 1, (&mut mask.head) as _));
             }
 
@@ -375,10 +658,19 @@ impl Window {
             let title = builder.title.as_ref();
             internal_set_title(c, xid, &connection.details.atoms, title);
 
+            if builder.accept_drops {
+                dnd::set_aware(c, &connection.details.dnd_atoms, xid);
+            }
+
+            let opacity = clamp_opacity(builder.opacity);
+            if opacity < 1.0 {
+                internal_set_opacity(c, xid, &connection.details.atoms, opacity);
+            }
+
             // If hostname is known, get PID of current process and write that to _NET_WM_PID
             // But don't write either of these properties if hostname is not known, because:
             // "If _NET_WM_PID is set, the ICCCM-specified property WM_CLIENT_MACHINE MUST also be set." - EWMH spec
-            if let Some(hostname) = hostname {
+            if let Some(hostname) = connection.hostname.as_ref() {
                 let pid = getpid();
                 let _ = xcb_change_property(
                     c,
@@ -449,14 +741,53 @@ impl Window {
             }
 
             let root = (*connection.details.screen).root;
+            #[cfg(feature = "input")]
+            let compose = connection.keyboard.as_ref().and_then(|keyboard| keyboard.create_compose());
+            #[cfg(feature = "input")]
+            let layout_serial = connection.keyboard.as_ref().map_or(0, Keyboard::layout_serial);
+            let clipboard_serials = [
+                connection.clipboard.serial(Selection::Clipboard),
+                connection.clipboard.serial(Selection::Primary),
+            ];
             std::mem::drop(connection_mtx);
 
             let window = Window {
                 connection: builder.connection,
                 details: WindowDetails {
                     handle: xid,
+                    pointer: Mutex::new(PointerState {
+                        grab: CursorGrab::None,
+                        #[cfg(feature = "input")]
+                        mode: CursorMode::Normal,
+                        grabbed: false,
+                    }),
                     event_buffer: Vec::with_capacity(QUEUE_SIZE),
+                    focused: false,
+                    #[cfg(feature = "input")]
+                    modifiers: Modifiers::empty(),
                     parent: root,
+                    #[cfg(feature = "input")]
+                    scroll: None,
+                    #[cfg(feature = "input")]
+                    touches: Touches::default(),
+                    #[cfg(feature = "input")]
+                    pinch_scale: 1.0,
+                    #[cfg(feature = "input")]
+                    pens: HashMap::new(),
+                    #[cfg(feature = "input")]
+                    pen_proximity: None,
+                    #[cfg(feature = "input")]
+                    ime: Mutex::new(None),
+                    #[cfg(feature = "input")]
+                    ime_cursor_area: Mutex::new(None),
+                    #[cfg(feature = "input")]
+                    compose,
+                    #[cfg(feature = "input")]
+                    layout_serial,
+                    clipboard_serials,
+                    drop_target: builder.accept_drops.then(DropTarget::default),
+                    drag_source: Mutex::new(None),
+                    opacity: Mutex::new(opacity),
                     position: (x, y),
                     style: Arc::new(Mutex::new(builder.style)),
                     size: (width, height),
@@ -485,6 +816,11 @@ impl Window {
             let Connection {
                 details: connection_details,
                 event_buffer: map,
+                clipboard,
+                #[cfg(feature = "input")]
+                ime,
+                #[cfg(feature = "input")]
+                keyboard,
                 ..
             } = &mut *connection_;
             let c = connection_details.connection;
@@ -498,7 +834,7 @@ impl Window {
             // `None` result, so it's better to check and take no action if there's no queue to copy from...
             if let Some(queue) = map.get_mut(&window_details.handle) {
                 for event in queue.iter().copied() {
-                    process_event(event, window_details, connection_details);
+                    process_event(event, window_details, connection_details, #[cfg(feature = "input")] keyboard);
                 }
                 queue.clear();
             }
@@ -509,11 +845,16 @@ impl Window {
             // Call `poll_event` once, which populates XCB's internal linked list from the connection
             let event = xcb_poll_for_event(c);
             if !event.is_null() {
-                if let Some(window) = get_event_window(event, connection_details) {
-                    if window == window_details.handle {
-                        process_event(event, window_details, connection_details);
-                    } else if let Some(queue) = map.get_mut(&window) {
-                        queue.push(event);
+                #[cfg(feature = "input")]
+                if let Some(keyboard) = keyboard {
+                    keyboard.process_event(event);
+                }
+                if !clipboard.process_event(event) {
+                    match get_event_window(event, connection_details) {
+                        Some(window) if window == window_details.handle => {
+                            process_event(event, window_details, connection_details, #[cfg(feature = "input")] keyboard)
+                        },
+                        window => queue_event(event, window, map),
                     }
                 }
             }
@@ -521,14 +862,153 @@ impl Window {
             loop {
                 let event = xcb_poll_for_queued_event(c);
                 if event.is_null() { break }
-                if let Some(window) = get_event_window(event, connection_details) {
-                    if window == window_details.handle {
-                        process_event(event, window_details, connection_details);
-                    } else if let Some(queue) = map.get_mut(&window) {
-                        queue.push(event);
+                #[cfg(feature = "input")]
+                if let Some(keyboard) = keyboard {
+                    keyboard.process_event(event);
+                }
+                if clipboard.process_event(event) { continue }
+                match get_event_window(event, connection_details) {
+                    Some(window) if window == window_details.handle => {
+                        process_event(event, window_details, connection_details, #[cfg(feature = "input")] keyboard)
+                    },
+                    window => queue_event(event, window, map),
+                }
+            }
+
+            // Layout changes aren't specific to any window, so every window gets told about them when it next polls
+            #[cfg(feature = "input")]
+            if let Some(keyboard) = keyboard.as_ref().filter(|k| k.layout_serial() != window_details.layout_serial) {
+                window_details.layout_serial = keyboard.layout_serial();
+                window_details.event_buffer.push(Event::KeyboardLayoutChanged);
+            }
+
+            // Same goes for clipboard changes
+            for &selection in [Selection::Clipboard, Selection::Primary].iter() {
+                let serial = clipboard.serial(selection);
+                if window_details.clipboard_serials[selection as usize] != serial {
+                    window_details.clipboard_serials[selection as usize] = serial;
+                    window_details.event_buffer.push(Event::ClipboardChanged { selection });
+                }
+            }
+
+            // Retry the pointer grab if someone else was holding the pointer when we last tried to take it
+            if window_details.focused && !mutex_lock(&window_details.pointer).grabbed {
+                update_pointer_grab(connection_details, window_details);
+            }
+
+            // A drop whose target has gone quiet has to end sometime, and there won't be an event to tell us when
+            drag_timeout(window_details, connection_details);
+
+            // Pick up anything the input method has sent back in response to those events
+            #[cfg(feature = "input")]
+            if let Some(Some(ime)) = ime {
+                ime.pump();
+                if let Some(context) = mutex_lock(&window_details.ime).as_mut() {
+                    context.take_events(&mut window_details.event_buffer);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn set_cursor_grab(&self, grab: CursorGrab) {
+        let mut connection_ = mutex_lock(&self.connection.0);
+        let connection = &mut connection_;
+        let mut g = mutex_lock(&self.details.pointer);
+        g.grab = grab;
+        std::mem::drop(g);
+        unsafe { update_pointer_grab(&connection.details, &self.details) };
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn set_cursor_mode(&self, mode: CursorMode) {
+        let mut connection_ = mutex_lock(&self.connection.0);
+        let connection = &mut connection_;
+        let mut g = mutex_lock(&self.details.pointer);
+        g.mode = mode;
+        std::mem::drop(g);
+        unsafe { update_pointer_grab(&connection.details, &self.details) };
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn set_ime_allowed(&self, allowed: bool) {
+        let mut connection = mutex_lock(&self.connection.0);
+        let mut context = mutex_lock(&self.details.ime);
+        unsafe {
+            if allowed && context.is_none() {
+                let ime = connection.ime.get_or_insert_with(|| Ime::new());
+                *context = ime.as_mut().and_then(|ime| ime.create_context(self.details.handle.into()));
+                if let Some(context) = context.as_mut() {
+                    context.set_focus(self.details.focused);
+                    if let Some((position, size)) = *mutex_lock(&self.details.ime_cursor_area) {
+                        context.set_cursor_area(position, size);
+                    }
+                }
+            }
+            if let Some(context) = context.as_mut() {
+                context.set_allowed(allowed);
+            }
+        }
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn set_ime_cursor_area(&self, position: (i16, i16), size: (u16, u16)) {
+        let _connection = mutex_lock(&self.connection.0);
+        *mutex_lock(&self.details.ime_cursor_area) = Some((position, size));
+        if let Some(context) = mutex_lock(&self.details.ime).as_mut() {
+            unsafe { context.set_cursor_area(position, size) };
+        }
+    }
+
+    pub(crate) fn cursor_position(&self) -> Option<(i16, i16)> {
+        let connection = mutex_lock(&self.connection.0);
+        unsafe { query_pointer(connection.details.connection, self.details.handle) }
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn mouse_buttons(&self) -> Vec<MouseButton> {
+        let connection = mutex_lock(&self.connection.0);
+        let mut buttons = Vec::new();
+        unsafe {
+            let reply = query_xi_pointer(connection.details.connection, self.details.handle);
+            if reply.is_null() {
+                return buttons;
+            }
+            let mask = std::slice::from_raw_parts(
+                reply.add(1) as *const u32,
+                (*reply).buttons_len.into(),
+            );
+            for (i, word) in mask.iter().enumerate() {
+                for bit in 0..32 {
+                    if word & (1 << bit) != 0 {
+                        if let Some(button) = mouse_button(i as u32 * 32 + bit) {
+                            buttons.push(button);
+                        }
                     }
                 }
             }
+            free(reply.cast());
+        }
+        buttons
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn modifiers(&self) -> Modifiers {
+        let connection = mutex_lock(&self.connection.0);
+        unsafe {
+            let reply = query_xi_pointer(connection.details.connection, self.details.handle);
+            if reply.is_null() {
+                return Modifiers::empty();
+            }
+            let modifiers = modifiers_from_mask((*reply).mods.effective);
+            free(reply.cast());
+            modifiers
+        }
+    }
+
+    pub(crate) fn set_cursor_position(&self, (x, y): (i16, i16)) {
+        let connection = mutex_lock(&self.connection.0);
+        unsafe {
+            let _ = xcb_warp_pointer(connection.details.connection, 0, self.details.handle, 0, 0, 0, 0, x, y);
         }
     }
 
@@ -589,6 +1069,19 @@ impl Window {
         }
     }
 
+    pub(crate) fn opacity(&self) -> f32 {
+        *mutex_lock(&self.details.opacity)
+    }
+
+    pub(crate) fn set_opacity(&self, opacity: f32) {
+        let mut connection_ = mutex_lock(&self.connection.0);
+        let connection = &mut connection_;
+        let opacity = clamp_opacity(opacity);
+        *mutex_lock(&self.details.opacity) = opacity;
+        let c = connection.details.connection;
+        unsafe { internal_set_opacity(c, self.details.handle, &connection.details.atoms, opacity) };
+    }
+
     pub(crate) fn set_visible(&self, visible: bool) {
         let mut connection_ = mutex_lock(&self.connection.0);
         let connection = &mut connection_;
@@ -601,6 +1094,34 @@ impl Window {
         }
     }
 
+    pub(crate) fn start_drag(&self, data: DragData, allowed_actions: &[DragAction]) -> Result<(), Error> {
+        let formats = match data {
+            DragData::Paths(paths) => vec![("text/uri-list".to_owned(), dnd::uri_list(&paths))],
+            DragData::Data(formats) => formats,
+        };
+        if formats.is_empty() || allowed_actions.is_empty() {
+            return Err(Error::Invalid)
+        }
+        let formats = formats.iter().map(|(name, data)| (name.as_str(), data.as_slice())).collect::<Vec<_>>();
+
+        let mut connection = mutex_lock(&self.connection.0);
+        let mut drag = mutex_lock(&self.details.drag_source);
+        // A drag that's been dropped can be replaced, in case the target never finishes with it
+        if drag.as_ref().map_or(false, |drag| !drag.is_dropped()) {
+            return Err(Error::Invalid)
+        }
+        unsafe {
+            let types = {
+                let (clipboard, mut route) = connection.clipboard_parts();
+                clipboard.set_drag_data(&formats, &mut route)?
+            };
+            let details = &connection.details;
+            let (c, atoms) = (details.connection, &details.dnd_atoms);
+            *drag = Some(DragSource::start(c, atoms, self.details.handle, types, allowed_actions)?);
+        }
+        Ok(())
+    }
+
     pub(crate) fn xid(&self) -> xcb_window_t {
         self.details.handle
     }
@@ -611,6 +1132,12 @@ impl Drop for Window {
         let mut connection_ = mutex_lock(&self.connection.0);
         let connection = &mut connection_;
         unsafe {
+            self.details.focused = false;
+            update_pointer_grab(&connection.details, &self.details);
+            #[cfg(feature = "input")]
+            if let (Some(Some(ime)), Some(context)) = (connection.ime.as_mut(), mutex_lock(&self.details.ime).take()) {
+                ime.destroy_context(&context);
+            }
             let _ = xcb_destroy_window(connection.details.connection, self.details.handle);
             let _ = xcb_flush(connection.details.connection);
         }
@@ -691,8 +1218,70 @@ unsafe fn set_wm_normal_hints(c: *mut xcb_connection_t, details: &WindowDetails,
     );
 }
 
+// Passes an event on to the drag out of the window, if there is one, and forgets about the drag once it's over
+unsafe fn drag_event(ev: *mut xcb_generic_event_t, window: &mut WindowDetails, details: &ConnectionDetails) {
+    let mut drag = mutex_lock(&window.drag_source);
+    let source = match drag.as_mut() {
+        Some(source) => source,
+        None => return,
+    };
+    let was_dropped = source.is_dropped();
+    let (c, root) = (details.connection, (*details.screen).root);
+    let over = source.process_event(c, &details.dnd_atoms, root, window.handle, ev, &mut window.event_buffer);
+    let dropped = source.is_dropped();
+    if over {
+        *drag = None;
+    }
+    std::mem::drop(drag);
+    // Releasing the pointer ends our grab, along with any the window was holding for `set_cursor_grab`
+    if !was_dropped && (over || dropped) {
+        update_pointer_grab(details, window);
+    }
+}
+
+// Ends the drag out of the window if it's been dropped and the target hasn't finished with it in time
+unsafe fn drag_timeout(window: &mut WindowDetails, details: &ConnectionDetails) {
+    let mut drag = mutex_lock(&window.drag_source);
+    if let Some(source) = drag.as_mut() {
+        let c = details.connection;
+        if source.check_timeout(c, &details.dnd_atoms, window.handle, &mut window.event_buffer) {
+            *drag = None;
+        }
+    }
+}
+
+// Lets go of the mouse button as far as a drag out of the window is concerned, since there's no real way to do that
+// without XTest. With no event mask, SendEvent delivers it to whoever created the window, which is us.
+#[cfg(test)]
+pub(crate) fn send_button_release(connection: &connection::Connection, xid: xcb_window_t) {
+    let connection = mutex_lock(&connection.0);
+    let details = &connection.details;
+    let event = xcb_button_release_event_t {
+        response_type: XCB_BUTTON_RELEASE,
+        detail: 1,
+        sequence: 0,
+        time: XCB_CURRENT_TIME,
+        root: unsafe { (*details.screen).root },
+        event: xid,
+        child: XCB_WINDOW_NONE,
+        root_x: 0,
+        root_y: 0,
+        event_x: 0,
+        event_y: 0,
+        state: 0,
+        same_screen: 1,
+        _pad0: 0,
+    };
+    unsafe {
+        let c = details.connection;
+        xcb_discard_reply(c, xcb_send_event_checked(c, 0, xid, 0, (&event as *const _) as *const i8));
+        let _ = xcb_flush(c);
+    }
+}
+
 // Gets the window an event is destined for, if any. `None` results should be discarded.
-unsafe fn get_event_window(ev: *mut xcb_generic_event_t, details: &ConnectionDetails) -> Option<xcb_window_t> {
+// This also keeps track of which window has input focus, since that's where raw input events get sent.
+unsafe fn get_event_window(ev: *mut xcb_generic_event_t, details: &mut ConnectionDetails) -> Option<xcb_window_t> {
     #[cfg(not(feature = "input"))]
     { _ = details }
     match (*ev).response_type & !(1 << 7) {
@@ -703,15 +1292,38 @@ unsafe fn get_event_window(ev: *mut xcb_generic_event_t, details: &ConnectionDet
         XCB_REPARENT_NOTIFY => Some((*(ev as *mut xcb_reparent_notify_event_t)).window),
         XCB_CONFIGURE_NOTIFY => Some((*(ev as *mut xcb_configure_notify_event_t)).window),
         XCB_PROPERTY_NOTIFY => Some((*(ev as *mut xcb_property_notify_event_t)).window),
+        XCB_SELECTION_NOTIFY => Some((*(ev as *mut xcb_selection_notify_event_t)).requestor),
+        // We only get core pointer and keyboard events while we've grabbed them for a drag
+        XCB_MOTION_NOTIFY | XCB_BUTTON_RELEASE | XCB_KEY_PRESS => {
+            Some((*(ev as *mut xcb_button_release_event_t)).event)
+        },
         #[cfg(feature = "input")]
         XCB_GE_GENERIC => {
             let event = &*(ev as *mut xcb_ge_generic_event_t);
             if event.extension == details.extensions.xinput {
                 match event.event_type & !(1 << 7) {
                     XCB_INPUT_KEY_PRESS | XCB_INPUT_KEY_RELEASE | XCB_INPUT_BUTTON_PRESS | XCB_INPUT_BUTTON_RELEASE | XCB_INPUT_MOTION
+                    | XCB_INPUT_TOUCH_BEGIN | XCB_INPUT_TOUCH_UPDATE | XCB_INPUT_TOUCH_END
                         => Some((*(ev as *mut xcb_input_button_press_event_t)).event),
-                    XCB_INPUT_ENTER | XCB_INPUT_LEAVE | XCB_INPUT_FOCUS_IN | XCB_INPUT_FOCUS_OUT
+                    XCB_INPUT_ENTER | XCB_INPUT_LEAVE
                         => Some((*(ev as *mut xcb_input_enter_event_t)).event),
+                    XCB_INPUT_GESTURE_PINCH_BEGIN | XCB_INPUT_GESTURE_PINCH_UPDATE | XCB_INPUT_GESTURE_PINCH_END
+                        => Some((*(ev as *mut xcb_input_gesture_pinch_begin_event_t)).event),
+                    XCB_INPUT_GESTURE_SWIPE_BEGIN | XCB_INPUT_GESTURE_SWIPE_UPDATE | XCB_INPUT_GESTURE_SWIPE_END
+                        => Some((*(ev as *mut xcb_input_gesture_swipe_begin_event_t)).event),
+                    e @ XCB_INPUT_FOCUS_IN | e @ XCB_INPUT_FOCUS_OUT => {
+                        let window = (*(ev as *mut xcb_input_enter_event_t)).event;
+                        if e == XCB_INPUT_FOCUS_IN {
+                            details.input_focus = window;
+                        } else if details.input_focus == window {
+                            details.input_focus = 0;
+                        }
+                        Some(window)
+                    },
+                    XCB_INPUT_RAW_MOTION => match details.input_focus {
+                        0 => None,
+                        window => Some(window),
+                    },
                     _ => None,
                 }
             } else {
@@ -722,8 +1334,26 @@ unsafe fn get_event_window(ev: *mut xcb_generic_event_t, details: &ConnectionDet
     }
 }
 
+// Stores an event in the queue of the window it's destined for, so it can be processed next time that window polls.
+// Events which aren't destined for any of our windows are freed.
+unsafe fn queue_event(
+    ev: *mut xcb_generic_event_t,
+    window: Option<xcb_window_t>,
+    map: &mut HashMap<xcb_window_t, Vec<*mut xcb_generic_event_t>>,
+) {
+    match window.and_then(|w| map.get_mut(&w)) {
+        Some(queue) => queue.push(ev),
+        None => free(ev.cast()),
+    }
+}
+
 // This function assumes the given event is destined for the given Window - check first with get_event_window
-unsafe fn process_event(ev: *mut xcb_generic_event_t, window: &mut WindowDetails, details: &ConnectionDetails) {
+unsafe fn process_event(
+    ev: *mut xcb_generic_event_t,
+    window: &mut WindowDetails,
+    details: &ConnectionDetails,
+    #[cfg(feature = "input")] keyboard: &mut Option<Keyboard>,
+) {
     let is_send_event = ((*ev).response_type >> 7) != 0;
     match (*ev).response_type & !(1 << 7) {
         XCB_CLIENT_MESSAGE => {
@@ -743,12 +1373,24 @@ unsafe fn process_event(ev: *mut xcb_generic_event_t, window: &mut WindowDetails
                     ));
                     let _ = xcb_flush(details.connection); // Makes sure the event is processed before we free it
                 }
+            } else {
+                if let Some(target) = window.drop_target.as_mut() {
+                    let (handle, position) = (window.handle, window.position);
+                    let c = details.connection;
+                    target.client_message(c, &details.dnd_atoms, handle, position, event, &mut window.event_buffer);
+                }
+                drag_event(ev, window, details);
             }
         },
-        e @ XCB_FOCUS_IN | e @ XCB_FOCUS_OUT => {
-            let state = e == XCB_FOCUS_IN;
-            window.event_buffer.push(Event::Focus(state));
+        XCB_MOTION_NOTIFY | XCB_BUTTON_RELEASE | XCB_KEY_PRESS => drag_event(ev, window, details),
+        XCB_SELECTION_NOTIFY => {
+            let event = &*(ev as *mut xcb_selection_notify_event_t);
+            if let Some(target) = window.drop_target.as_mut() {
+                let c = details.connection;
+                target.selection_notify(c, &details.dnd_atoms, window.handle, event, &mut window.event_buffer);
+            }
         },
+        e @ XCB_FOCUS_IN | e @ XCB_FOCUS_OUT => set_focus(window, details, e == XCB_FOCUS_IN),
         XCB_REPARENT_NOTIFY => {
             let event = &*(ev as *mut xcb_reparent_notify_event_t);
             window.parent = event.parent;
@@ -788,6 +1430,21 @@ unsafe fn process_event(ev: *mut xcb_generic_event_t, window: &mut WindowDetails
         },
         XCB_PROPERTY_NOTIFY => {
             let event = &*(ev as *mut xcb_property_notify_event_t);
+            if let Some(target) = window.drop_target.as_mut() {
+                let c = details.connection;
+                target.property_notify(c, &details.dnd_atoms, window.handle, event, &mut window.event_buffer);
+            }
+            if event.atom == details.atoms._net_wm_window_opacity {
+                // Compositors may let the user change this themselves, so we don't assume it's what we last set
+                let opacity = match x11::read_property(details.connection, window.handle, event.atom, false) {
+                    Some((XCB_ATOM_CARDINAL, data)) if data.len() == 4 => {
+                        let value = u32::from_ne_bytes([data[0], data[1], data[2], data[3]]);
+                        (f64::from(value) / f64::from(u32::MAX)) as f32
+                    },
+                    _ => 1.0,
+                };
+                *mutex_lock(&window.opacity) = opacity;
+            }
             if event.atom == details.atoms._net_wm_state {
                 match event.state {
                     XCB_PROPERTY_NEW_VALUE => {
@@ -860,7 +1517,12 @@ unsafe fn process_event(ev: *mut xcb_generic_event_t, window: &mut WindowDetails
             }
         },
         XCB_MAP_NOTIFY => window.event_buffer.push(Event::Visible(true)),
-        XCB_UNMAP_NOTIFY => window.event_buffer.push(Event::Visible(false)),
+        XCB_UNMAP_NOTIFY => {
+            window.event_buffer.push(Event::Visible(false));
+            // We won't hear about touches ending once the window is gone
+            #[cfg(feature = "input")]
+            window.touches.cancel(&mut window.event_buffer);
+        },
         #[cfg(feature = "input")]
         XCB_GE_GENERIC => {
             let event = &*(ev as *mut xcb_ge_generic_event_t);
@@ -869,81 +1531,243 @@ unsafe fn process_event(ev: *mut xcb_generic_event_t, window: &mut WindowDetails
                     e @ XCB_INPUT_KEY_PRESS | e @ XCB_INPUT_KEY_RELEASE => {
                         let is_press = e == XCB_INPUT_KEY_PRESS;
                         let event = &*(ev as *mut xcb_input_key_press_event_t);
-                        let mut xevent = XKeyEvent {
-                            r#type: 2,
+                        // Xlib's input method needs a complete event to work with
+                        let xevent = XKeyEvent {
+                            r#type: if is_press { KeyPress } else { KeyRelease },
                             serial: 0,
                             send_event: 0,
                             display: details.display,
-                            window: 0,
-                            root: 0,
-                            subwindow: 0,
-                            time: 0,
-                            x: 0,
-                            y: 0,
-                            x_root: 0,
-                            y_root: 0,
+                            window: event.event.into(),
+                            root: event.root.into(),
+                            subwindow: event.child.into(),
+                            time: event.time.into(),
+                            x: event.event_x >> 16,
+                            y: event.event_y >> 16,
+                            x_root: event.root_x >> 16,
+                            y_root: event.root_y >> 16,
                             state: event.mods.effective,
                             keycode: event.detail,
-                            same_screen: 0,
+                            same_screen: 1,
                         };
                         let repeat = (event.flags & XCB_INPUT_KEY_EVENT_FLAGS_KEY_REPEAT) != 0;
-                        let f = if is_press {
-                            if repeat { Event::KeyboardRepeat } else { Event::KeyboardDown }
+                        // The modifier state in the event is from before this key was pressed or released
+                        let modifiers = modifiers_from_mask(event.mods.effective);
+                        update_modifiers(window, modifiers);
+                        // With the evdev driver, which is all that's used nowadays, X keycodes are evdev codes + 8
+                        let keycode = event.detail;
+                        let scancode = ScanCode::from_evdev(keycode.saturating_sub(8));
+                        let f = |key| if is_press {
+                            if repeat {
+                                Event::KeyboardRepeat { key, scancode, keycode, modifiers }
+                            } else {
+                                Event::KeyboardDown { key, scancode, keycode, modifiers }
+                            }
                         } else {
-                            Event::KeyboardUp
+                            Event::KeyboardUp { key, scancode, keycode, modifiers }
                         };
-                        let unmodified_keysym = XLookupKeysym(&mut xevent, 0);
-                        let mut modified_keysym: KeySym = 0;
-                        let _ = XLookupString(
-                            &mut xevent,
-                            std::ptr::null_mut(),
-                            0,
-                            &mut modified_keysym,
-                            std::ptr::null_mut(),
-                        );
-
-                        if let Some(k) = keysym_to_key(unmodified_keysym, modified_keysym) {
-                            window.event_buffer.push(f(k));
-                        }
-
-                        if is_press {
-                            if let Ok(utf32) = u32::try_from(modified_keysym) {
-                                if let Some(ch) = char::from_u32(xkb_keysym_to_utf32(utf32)) {
-                                    if ch != '\0' {
-                                        window.event_buffer.push(Event::Input(ch));
+                        let (k, modified_keysym) = match keyboard {
+                            Some(keyboard) => {
+                                let (unmodified_keysym, modified_keysym) =
+                                    keyboard.key_get_syms(keycode, &event.mods, &event.group);
+                                let k = keysym_to_key(unmodified_keysym, modified_keysym)
+                                    .unwrap_or(Key::Unknown(unmodified_keysym));
+                                (k, modified_keysym)
+                            },
+                            // Without a keymap, the best we can do is say where the key is
+                            None => (scancode.qwerty_key().unwrap_or(Key::Unknown(0)), 0),
+                        };
+                        window.event_buffer.push(f(k));
+
+                        // Predict the effect of modifier keys rather than waiting for the next event to tell us.
+                        // The lock keys are left alone as whether they toggle depends on the keymap.
+                        let modifier = match k {
+                            Key::LeftShift | Key::RightShift => Modifiers::SHIFT,
+                            Key::LeftControl | Key::RightControl => Modifiers::CTRL,
+                            Key::LeftAlt | Key::RightAlt => Modifiers::ALT,
+                            Key::LeftSuper | Key::RightSuper => Modifiers::SUPER,
+                            _ => Modifiers::empty(),
+                        };
+                        let mut new_modifiers = modifiers;
+                        new_modifiers.set(modifier, is_press);
+                        update_modifiers(window, new_modifiers);
+
+                        let mut ime = mutex_lock(&window.ime);
+                        if let Some(context) = ime.as_mut().filter(|context| context.is_active()) {
+                            // The input method will send the key back later if it doesn't want it, see `Ime::pump`
+                            if let Some(text) = context.filter_key(&xevent) {
+                                window.event_buffer.extend(text.chars().map(Event::Input));
+                            }
+                        } else if is_press {
+                            let result = match window.compose.as_mut() {
+                                Some(compose) => compose.feed(modified_keysym),
+                                None => ComposeResult::Unhandled,
+                            };
+                            match result {
+                                // Without a keymap there's no keysym, and xkbcommon might not even be loaded
+                                ComposeResult::Unhandled if modified_keysym != 0 => {
+                                    if let Some(ch) = char::from_u32(xkb_keysym_to_utf32(modified_keysym)) {
+                                        if ch != '\0' {
+                                            window.event_buffer.push(Event::Input(ch));
+                                        }
                                     }
-                                }
+                                },
+                                ComposeResult::Unhandled => (),
+                                ComposeResult::Consumed => (),
+                                ComposeResult::Composed(text, _) if !text.is_empty() => {
+                                    window.event_buffer.extend(text.chars().map(Event::Input));
+                                },
+                                ComposeResult::Composed(_, keysym) => {
+                                    // Some sequences only produce a keysym, which may or may not have a character
+                                    match char::from_u32(xkb_keysym_to_utf32(keysym)).filter(|ch| *ch != '\0') {
+                                        Some(ch) => window.event_buffer.push(Event::Input(ch)),
+                                        // Otherwise it's a key of its own, so it's reported as being tapped
+                                        None => if keysym != 0 {
+                                            let key = keysym_to_key(keysym, keysym).unwrap_or(Key::Unknown(keysym));
+                                            window.event_buffer.extend([
+                                                Event::KeyboardDown { key, scancode, keycode, modifiers },
+                                                Event::KeyboardUp { key, scancode, keycode, modifiers },
+                                            ]);
+                                        },
+                                    }
+                                },
                             }
                         }
                     },
+                    e @ XCB_INPUT_TOUCH_BEGIN | e @ XCB_INPUT_TOUCH_UPDATE | e @ XCB_INPUT_TOUCH_END => {
+                        let event = &*(ev as *mut xcb_input_touch_begin_event_t);
+                        let position = (fp1616_to_f64(event.event_x), fp1616_to_f64(event.event_y));
+                        if let Some(touch) = window.touches.event(e, event.detail, position) {
+                            window.event_buffer.push(touch);
+                        }
+                    },
+                    e @ XCB_INPUT_GESTURE_PINCH_BEGIN
+                    | e @ XCB_INPUT_GESTURE_PINCH_UPDATE
+                    | e @ XCB_INPUT_GESTURE_PINCH_END => {
+                        let event = &*(ev as *mut xcb_input_gesture_pinch_begin_event_t);
+                        let cancelled = event.flags & XCB_INPUT_GESTURE_PINCH_EVENT_FLAGS_GESTURE_PINCH_CANCELLED != 0;
+                        // The server reports the total scale since the gesture started, so we work out the change
+                        let scale = fp1616_to_f64(event.scale);
+                        let delta_scale = match e {
+                            XCB_INPUT_GESTURE_PINCH_BEGIN => 0.0,
+                            _ => scale - window.pinch_scale,
+                        };
+                        window.pinch_scale = scale;
+                        window.event_buffer.push(Event::PinchGesture {
+                            delta_scale,
+                            delta_angle: fp1616_to_f64(event.delta_angle),
+                            phase: gesture_phase(e - XCB_INPUT_GESTURE_PINCH_BEGIN, cancelled),
+                        });
+                    },
+                    e @ XCB_INPUT_GESTURE_SWIPE_BEGIN
+                    | e @ XCB_INPUT_GESTURE_SWIPE_UPDATE
+                    | e @ XCB_INPUT_GESTURE_SWIPE_END => {
+                        let event = &*(ev as *mut xcb_input_gesture_swipe_begin_event_t);
+                        let cancelled = event.flags & XCB_INPUT_GESTURE_SWIPE_EVENT_FLAGS_GESTURE_SWIPE_CANCELLED != 0;
+                        window.event_buffer.push(Event::SwipeGesture {
+                            dx: fp1616_to_f64(event.delta_x),
+                            dy: fp1616_to_f64(event.delta_y),
+                            fingers: event.detail,
+                            phase: gesture_phase(e - XCB_INPUT_GESTURE_SWIPE_BEGIN, cancelled),
+                        });
+                    },
                     e @ XCB_INPUT_BUTTON_PRESS | e @ XCB_INPUT_BUTTON_RELEASE => {
-                        use crate::input::MouseButton;
+                        pen_event(details, window, ev.cast(), e);
                         let event = &*(ev as *mut xcb_input_button_press_event_t);
-                        let f = if e == XCB_INPUT_BUTTON_PRESS { Event::MouseDown } else { Event::MouseUp };
+                        let modifiers = modifiers_from_mask(event.mods.effective);
+                        update_modifiers(window, modifiers);
+                        let f = |button| if e == XCB_INPUT_BUTTON_PRESS {
+                            Event::MouseDown { button, modifiers }
+                        } else {
+                            Event::MouseUp { button, modifiers }
+                        };
                         match event.detail {
-                            1 => window.event_buffer.push(f(MouseButton::Left)),
-                            2 => window.event_buffer.push(f(MouseButton::Middle)),
-                            3 => window.event_buffer.push(f(MouseButton::Right)),
-                            4 if e == XCB_INPUT_BUTTON_PRESS => window.event_buffer.push(Event::ScrollUp),
-                            5 if e == XCB_INPUT_BUTTON_PRESS => window.event_buffer.push(Event::ScrollDown),
-                            _ => (),
+                            detail @ 4..=7 => if e == XCB_INPUT_BUTTON_PRESS {
+                                match detail {
+                                    4 => window.event_buffer.push(Event::ScrollUp),
+                                    5 => window.event_buffer.push(Event::ScrollDown),
+                                    _ => (),
+                                }
+                                // For devices with scroll valuators the server emulates these buttons,
+                                // but we already report those through the valuators in motion events
+                                if event.flags & XCB_INPUT_POINTER_EVENT_FLAGS_POINTER_EMULATED == 0 {
+                                    let (dx, dy) = match detail {
+                                        4 => (0.0, 1.0),
+                                        5 => (0.0, -1.0),
+                                        6 => (-1.0, 0.0),
+                                        _ => (1.0, 0.0),
+                                    };
+                                    window.event_buffer.push(Event::Scroll { dx, dy, unit: ScrollUnit::Lines });
+                                }
+                            },
+                            detail => if let Some(button) = mouse_button(detail) {
+                                window.event_buffer.push(f(button))
+                            },
                         }
                     },
                     XCB_INPUT_MOTION => {
+                        pen_event(details, window, ev.cast(), XCB_INPUT_MOTION);
+                        // Before XI 2.1 there are no scroll valuators, only the buttons 4 to 7
+                        if details.extensions.xinput_version >= (2, 1) {
+                            if let Some((dx, dy)) = motion_scroll(details.connection, window, ev.cast()) {
+                                window.event_buffer.push(Event::Scroll { dx, dy, unit: ScrollUnit::Lines });
+                            }
+                        }
                         let event = &*(ev as *mut xcb_input_motion_event_t);
-                        window.event_buffer.push(Event::MouseMove(((event.event_x >> 16) as _, (event.event_y >> 16) as _)))
+                        let xy = ((event.event_x >> 16) as u16, (event.event_y >> 16) as u16);
+                        if window.focused && mutex_lock(&window.pointer).mode == CursorMode::Locked {
+                            // Keep putting the cursor back in the centre, which causes another motion event that
+                            // we can just ignore
+                            let centre = (window.size.0 / 2, window.size.1 / 2);
+                            if xy != centre {
+                                warp_to_centre(details.connection, window);
+                            }
+                        } else {
+                            window.event_buffer.push(Event::MouseMove(xy))
+                        }
+                    },
+                    XCB_INPUT_RAW_MOTION => {
+                        let event = &*(ev as *mut xcb_input_raw_motion_event_t);
+                        let mask = std::slice::from_raw_parts(
+                            (ev as *const xcb_input_raw_motion_event_t).add(1) as *const u32,
+                            event.valuators_len.into(),
+                        );
+                        // Skip past the accelerated values to get to the raw ones
+                        let value_count = mask.iter().map(|x| x.count_ones() as usize).sum::<usize>();
+                        let mut value = (mask.as_ptr().add(mask.len()) as *const xcb_input_fp3232_t).add(value_count);
+                        // Valuators 0 and 1 are always the X and Y axes
+                        let mut delta = [0.0f64; 2];
+                        for (axis, d) in delta.iter_mut().enumerate() {
+                            if mask.first().map(|x| x & (1 << axis) != 0).unwrap_or(false) {
+                                *d = fp3232_to_f64(&*value);
+                                value = value.add(1);
+                            }
+                        }
+                        if delta != [0.0, 0.0] {
+                            window.event_buffer.push(Event::RawMouseMotion { dx: delta[0], dy: delta[1] });
+                        }
                     },
                     XCB_INPUT_ENTER => {
-                        let _event = &*(ev as *mut xcb_input_enter_event_t);
+                        let event = &*(ev as *mut xcb_input_enter_event_t);
+                        // The scroll valuators may have moved while the pointer was elsewhere
+                        if details.extensions.xinput_version >= (2, 1) {
+                            window.scroll = Some(query_scroll_device(details.connection, event.deviceid, event.sourceid));
+                        }
+                        // Likewise, tablets may have been plugged in or out, so look the pens up again
+                        window.pens.clear();
                         window.event_buffer.push(Event::MouseEnter);
                     },
                     XCB_INPUT_LEAVE => {
                         let _event = &*(ev as *mut xcb_input_leave_event_t);
+                        leave_pen_proximity(window);
                         window.event_buffer.push(Event::MouseLeave);
                     },
                     e @ XCB_INPUT_FOCUS_IN | e @ XCB_INPUT_FOCUS_OUT => {
-                        let state = e == XCB_INPUT_FOCUS_IN;
-                        window.event_buffer.push(Event::Focus(state))
+                        if e == XCB_INPUT_FOCUS_IN {
+                            // Modifiers may have been pressed or released while we were out of focus
+                            let event = &*(ev as *mut xcb_input_focus_in_event_t);
+                            update_modifiers(window, modifiers_from_mask(event.mods.effective));
+                        }
+                        set_focus(window, details, e == XCB_INPUT_FOCUS_IN)
                     },
                     _ => (),
                 }
@@ -954,6 +1778,445 @@ unsafe fn process_event(ev: *mut xcb_generic_event_t, window: &mut WindowDetails
     free(ev.cast());
 }
 
+#[cfg(feature = "input")]
+pub(super) fn modifiers_from_mask(mask: u32) -> Modifiers {
+    // These are the conventional mappings, as used by pretty much every keymap
+    let mut modifiers = Modifiers::empty();
+    modifiers.set(Modifiers::SHIFT, mask & XCB_MOD_MASK_SHIFT != 0);
+    modifiers.set(Modifiers::CAPS_LOCK, mask & XCB_MOD_MASK_LOCK != 0);
+    modifiers.set(Modifiers::CTRL, mask & XCB_MOD_MASK_CONTROL != 0);
+    modifiers.set(Modifiers::ALT, mask & XCB_MOD_MASK_1 != 0);
+    modifiers.set(Modifiers::NUM_LOCK, mask & XCB_MOD_MASK_2 != 0);
+    modifiers.set(Modifiers::SUPER, mask & XCB_MOD_MASK_4 != 0);
+    modifiers
+}
+
+#[cfg(feature = "input")]
+fn update_modifiers(window: &mut WindowDetails, modifiers: Modifiers) {
+    if window.modifiers != modifiers {
+        window.modifiers = modifiers;
+        window.event_buffer.push(Event::ModifiersChanged(modifiers));
+    }
+}
+
+unsafe fn set_focus(window: &mut WindowDetails, details: &ConnectionDetails, focused: bool) {
+    if window.focused != focused {
+        window.focused = focused;
+        update_pointer_grab(details, window);
+        #[cfg(feature = "input")]
+        if let Some(context) = mutex_lock(&window.ime).as_mut() {
+            context.set_focus(focused);
+        }
+        // Don't let a half-typed compose sequence carry over to when focus comes back
+        #[cfg(feature = "input")]
+        if let Some(compose) = window.compose.as_mut() {
+            compose.reset();
+        }
+    }
+    window.event_buffer.push(Event::Focus(focused));
+}
+
+// Grabs or releases the pointer according to the window's PointerState.
+// Grabs are only held while the window is focused, so that the user can still switch away from it.
+// assumes we hold connection lock
+unsafe fn update_pointer_grab(details: &ConnectionDetails, window: &WindowDetails) {
+    // A drag holds its own grab until it's dropped, and puts this one back afterwards, see `drag_event`
+    if mutex_lock(&window.drag_source).as_ref().map_or(false, |drag| !drag.is_dropped()) {
+        return
+    }
+    let c = details.connection;
+    let mut pointer = mutex_lock(&window.pointer);
+    #[cfg(feature = "input")]
+    let (locked, cursor) = match pointer.mode {
+        CursorMode::Normal => (false, XCB_CURSOR_NONE),
+        CursorMode::Locked => (true, details.blank_cursor),
+    };
+    #[cfg(not(feature = "input"))]
+    let (locked, cursor) = (false, XCB_CURSOR_NONE);
+    if window.focused && (locked || pointer.grab == CursorGrab::Confined) {
+        // GrabPointer fails with AlreadyGrabbed while the window manager or someone else is still holding the
+        // pointer, which is common just after focus changes, or with NotViewable if we're not mapped yet. Both are
+        // temporary, so if it fails, `poll_events` tries again until it works or the window loses focus.
+        // If we already hold the grab, this just changes the cursor.
+        let reply = xcb_grab_pointer_reply(c, xcb_grab_pointer(
+            c,
+            1,
+            window.handle,
+            0,
+            XCB_GRAB_MODE_ASYNC,
+            XCB_GRAB_MODE_ASYNC,
+            window.handle,
+            cursor,
+            XCB_CURRENT_TIME,
+        ), std::ptr::null_mut());
+        if reply.is_null() {
+            return
+        }
+        pointer.grabbed = (*reply).status == XCB_GRAB_STATUS_SUCCESS;
+        free(reply.cast());
+        #[cfg(feature = "input")]
+        if locked && pointer.grabbed {
+            warp_to_centre(c, window);
+        }
+    } else if pointer.grabbed {
+        let _ = xcb_ungrab_pointer(c, XCB_CURRENT_TIME);
+        pointer.grabbed = false;
+    }
+}
+
+#[cfg(feature = "input")]
+unsafe fn warp_to_centre(c: *mut xcb_connection_t, window: &WindowDetails) {
+    let (width, height) = window.size;
+    let _ = xcb_warp_pointer(c, 0, window.handle, 0, 0, 0, 0, (width / 2) as i16, (height / 2) as i16);
+}
+
+// Queries the client pointer through XI2, which unlike the core protocol reports every button,
+// along with the state of the keyboard paired with it. The caller must free the reply.
+#[cfg(feature = "input")]
+unsafe fn query_xi_pointer(c: *mut xcb_connection_t, xid: xcb_window_t) -> *mut xcb_input_xi_query_pointer_reply_t {
+    let client_pointer = xcb_input_xi_get_client_pointer_reply(
+        c,
+        xcb_input_xi_get_client_pointer(c, XCB_NONE as _),
+        std::ptr::null_mut(),
+    );
+    if client_pointer.is_null() {
+        return std::ptr::null_mut();
+    }
+    let deviceid = (*client_pointer).deviceid;
+    free(client_pointer.cast());
+    xcb_input_xi_query_pointer_reply(c, xcb_input_xi_query_pointer(c, xid, deviceid), std::ptr::null_mut())
+}
+
+// Gets the pointer position relative to a window. Returns `None` if the pointer isn't on the same screen as the window.
+unsafe fn query_pointer(c: *mut xcb_connection_t, xid: xcb_window_t) -> Option<(i16, i16)> {
+    let reply = xcb_query_pointer_reply(c, xcb_query_pointer(c, xid), std::ptr::null_mut());
+    if reply.is_null() {
+        return None;
+    }
+    let r = &*reply;
+    let result = if r.same_screen != 0 { Some((r.win_x, r.win_y)) } else { None };
+    free(reply.cast());
+    result
+}
+
+// There's no "invisible" cursor in X, so we make one from an empty 1x1 bitmap
+#[cfg(feature = "input")]
+unsafe fn create_blank_cursor(c: *mut xcb_connection_t, root: xcb_window_t) -> xcb_cursor_t {
+    let pixmap = xcb_generate_id(c);
+    let gc = xcb_generate_id(c);
+    let cursor = xcb_generate_id(c);
+    let _ = xcb_create_pixmap(c, 1, pixmap, root, 1, 1);
+    // A new pixmap's contents are undefined, so it needs clearing (the default foreground of a GC is 0)
+    let _ = xcb_create_gc(c, gc, pixmap, 0, std::ptr::null());
+    let rect = xcb_rectangle_t { x: 0, y: 0, width: 1, height: 1 };
+    let _ = xcb_poly_fill_rectangle(c, pixmap, gc, 1, &rect);
+    let _ = xcb_create_cursor(c, cursor, pixmap, pixmap, 0, 0, 0, 0, 0, 0, 0, 0);
+    let _ = xcb_free_gc(c, gc);
+    let _ = xcb_free_pixmap(c, pixmap);
+    cursor
+}
+
+#[cfg(feature = "input")]
+fn fp3232_to_f64(value: &xcb_input_fp3232_t) -> f64 {
+    f64::from(value.integral) + f64::from(value.frac) / 4294967296.0
+}
+
+#[cfg(feature = "input")]
+fn fp1616_to_f64(value: xcb_input_fp1616_t) -> f64 {
+    f64::from(value) / 65536.0
+}
+
+// Gesture events come in groups of begin, update and end, so this takes the offset into the group
+#[cfg(feature = "input")]
+fn gesture_phase(offset: u16, cancelled: bool) -> TouchPhase {
+    match offset {
+        0 => TouchPhase::Started,
+        1 => TouchPhase::Moved,
+        _ if cancelled => TouchPhase::Cancelled,
+        _ => TouchPhase::Ended,
+    }
+}
+
+// Finds the scroll valuators of a device, along with their current positions.
+#[cfg(feature = "input")]
+unsafe fn query_scroll_device(
+    c: *mut xcb_connection_t,
+    deviceid: xcb_input_device_id_t,
+    sourceid: xcb_input_device_id_t,
+) -> ScrollDevice {
+    let mut device = ScrollDevice { deviceid, sourceid, valuators: Vec::new() };
+    let reply = xcb_input_xi_query_device_reply(c, xcb_input_xi_query_device(c, deviceid), std::ptr::null_mut());
+    if reply.is_null() {
+        return device;
+    }
+    if (*reply).num_infos > 0 {
+        let info = reply.add(1) as *const xcb_input_xi_device_info_t;
+        let name_len = (usize::from((*info).name_len) + 3) & !3;
+        let mut class = (info.add(1) as *const u8).add(name_len) as *const xcb_input_device_class_t;
+        let mut values = Vec::new();
+        for _ in 0..(*info).num_classes {
+            match (*class).r#type {
+                XCB_INPUT_DEVICE_CLASS_TYPE_VALUATOR => {
+                    let valuator = &*(class as *const xcb_input_valuator_class_t);
+                    values.push((valuator.number, fp3232_to_f64(&valuator.value)));
+                },
+                XCB_INPUT_DEVICE_CLASS_TYPE_SCROLL => {
+                    let scroll = &*(class as *const xcb_input_scroll_class_t);
+                    let increment = fp3232_to_f64(&scroll.increment);
+                    if increment != 0.0 {
+                        device.valuators.push(ScrollValuator {
+                            number: scroll.number,
+                            horizontal: scroll.scroll_type == XCB_INPUT_SCROLL_TYPE_HORIZONTAL,
+                            increment,
+                            value: 0.0,
+                        });
+                    }
+                },
+                _ => (),
+            }
+            if (*class).len == 0 {
+                break
+            }
+            class = (class as *const u8).add(usize::from((*class).len) * 4) as *const xcb_input_device_class_t;
+        }
+        for valuator in device.valuators.iter_mut() {
+            if let Some((_, value)) = values.iter().find(|(number, _)| *number == valuator.number) {
+                valuator.value = *value;
+            }
+        }
+    }
+    free(reply.cast());
+    device
+}
+
+// Reads the scroll valuators out of a motion event, returning how far it scrolled in lines, if at all.
+#[cfg(feature = "input")]
+unsafe fn motion_scroll(
+    c: *mut xcb_connection_t,
+    window: &mut WindowDetails,
+    event: *const xcb_input_motion_event_t,
+) -> Option<(f64, f64)> {
+    let e = &*event;
+    // A different physical device may have started moving the pointer, in which case we need its valuators.
+    // The values we get from the query can be newer than the ones in this event, so don't report a delta for it.
+    let changed = match &window.scroll {
+        Some(device) => device.deviceid != e.deviceid || device.sourceid != e.sourceid,
+        None => true,
+    };
+    if changed {
+        window.scroll = Some(query_scroll_device(c, e.deviceid, e.sourceid));
+    }
+    let device = window.scroll.as_mut()?;
+    if device.valuators.is_empty() {
+        return None;
+    }
+
+    let mut delta = (0.0, 0.0);
+    for (number, new_value) in event_valuators(event) {
+        if let Some(valuator) = device.valuators.iter_mut().find(|v| v.number == number) {
+            let lines = (new_value - valuator.value) / valuator.increment;
+            valuator.value = new_value;
+            // Valuators increase when scrolling down, but we report up as positive
+            if valuator.horizontal {
+                delta.0 += lines;
+            } else {
+                delta.1 -= lines;
+            }
+        }
+    }
+    if changed || delta == (0.0, 0.0) { None } else { Some(delta) }
+}
+
+// Returns the number and value of each valuator included in a device event
+#[cfg(feature = "input")]
+unsafe fn event_valuators(event: *const xcb_input_motion_event_t) -> Vec<(u16, f64)> {
+    // The fixed-size event is followed by the button mask, then the valuator mask, then one value per set bit
+    let e = &*event;
+    let mask = std::slice::from_raw_parts(
+        (event.add(1) as *const u32).add(e.buttons_len.into()),
+        e.valuators_len.into(),
+    );
+    let mut value = mask.as_ptr().add(mask.len()) as *const xcb_input_fp3232_t;
+    let mut valuators = Vec::new();
+    for number in 0..mask.len() * 32 {
+        if mask[number / 32] & (1 << (number % 32)) != 0 {
+            valuators.push((number as u16, fp3232_to_f64(&*value)));
+            value = value.add(1);
+        }
+    }
+    valuators
+}
+
+// Works out whether a device is a pen or eraser, going by whether it senses pressure, and finds its pressure and tilt
+// valuators. X has no better way of telling a pen from its eraser than the device's name.
+#[cfg(feature = "input")]
+unsafe fn query_pen_device(details: &ConnectionDetails, deviceid: xcb_input_device_id_t) -> Option<PenDevice> {
+    let c = details.connection;
+    let reply = xcb_input_xi_query_device_reply(c, xcb_input_xi_query_device(c, deviceid), std::ptr::null_mut());
+    if reply.is_null() {
+        return None
+    }
+    let mut device = None;
+    if (*reply).num_infos > 0 {
+        let info = reply.add(1) as *const xcb_input_xi_device_info_t;
+        let mut pressure = None;
+        let mut tilt = [None, None];
+        let name_len = (usize::from((*info).name_len) + 3) & !3;
+        let mut class = (info.add(1) as *const u8).add(name_len) as *const xcb_input_device_class_t;
+        for _ in 0..(*info).num_classes {
+            if (*class).r#type == XCB_INPUT_DEVICE_CLASS_TYPE_VALUATOR {
+                let valuator = &*(class as *const xcb_input_valuator_class_t);
+                let slot = match valuator.label {
+                    label if label == details.atoms.abs_pressure => Some(&mut pressure),
+                    label if label == details.atoms.abs_tilt_x => Some(&mut tilt[0]),
+                    label if label == details.atoms.abs_tilt_y => Some(&mut tilt[1]),
+                    _ => None,
+                };
+                if let Some(slot) = slot {
+                    *slot = Some(PenValuator {
+                        number: valuator.number,
+                        min: fp3232_to_f64(&valuator.min),
+                        max: fp3232_to_f64(&valuator.max),
+                        value: fp3232_to_f64(&valuator.value),
+                    });
+                }
+            }
+            if (*class).len == 0 {
+                break
+            }
+            class = (class as *const u8).add(usize::from((*class).len) * 4) as *const xcb_input_device_class_t;
+        }
+        if let Some(pressure) = pressure {
+            let name = std::slice::from_raw_parts(info.add(1) as *const u8, (*info).name_len.into());
+            let tool = if String::from_utf8_lossy(name).to_lowercase().contains("eraser") {
+                PenTool::Eraser
+            } else {
+                PenTool::Pen
+            };
+            device = Some(PenDevice { tool, pressure, tilt, buttons: 0 });
+        }
+    }
+    free(reply.cast());
+    device
+}
+
+// Reports a motion or button event as a pen event if it came from a pen
+#[cfg(feature = "input")]
+unsafe fn pen_event(
+    details: &ConnectionDetails,
+    window: &mut WindowDetails,
+    event: *const xcb_input_motion_event_t,
+    event_type: u16,
+) {
+    let e = &*event;
+    let pen = match window.pens.entry(e.sourceid).or_insert_with(|| query_pen_device(details, e.sourceid)) {
+        Some(pen) => pen,
+        None => {
+            // Something other than a pen is moving the pointer, so the pen can't still be there
+            leave_pen_proximity(window);
+            return
+        },
+    };
+
+    for (number, value) in event_valuators(event) {
+        let valuators = std::iter::once(&mut pen.pressure).chain(pen.tilt.iter_mut().flatten());
+        for valuator in valuators.filter(|valuator| valuator.number == number) {
+            valuator.value = value;
+        }
+    }
+    // Button 1 is the tip touching the tablet, and buttons 2 and 3 are on the barrel
+    let pressed = event_type == XCB_INPUT_BUTTON_PRESS;
+    let phase = match (event_type, e.detail) {
+        (XCB_INPUT_BUTTON_PRESS, 1) | (XCB_INPUT_BUTTON_RELEASE, 1) => {
+            if pressed { TouchPhase::Started } else { TouchPhase::Ended }
+        },
+        (XCB_INPUT_BUTTON_PRESS, button @ 2..=3) | (XCB_INPUT_BUTTON_RELEASE, button @ 2..=3) => {
+            let bit = 1 << (button - 2);
+            if pressed { pen.buttons |= bit } else { pen.buttons &= !bit }
+            TouchPhase::Moved
+        },
+        _ => TouchPhase::Moved,
+    };
+
+    // Scales a valuator's value into 0.0..=1.0
+    let normalise = |valuator: &PenValuator| if valuator.max > valuator.min {
+        ((valuator.value - valuator.min) / (valuator.max - valuator.min)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let pressure = normalise(&pen.pressure);
+    let tilt = |valuator: &Option<PenValuator>| valuator.as_ref().map_or(0.0, |v| normalise(v) * 2.0 - 1.0);
+    let event = Event::Pen {
+        position: (fp1616_to_f64(e.event_x), fp1616_to_f64(e.event_y)),
+        pressure,
+        tilt: (tilt(&pen.tilt[0]), tilt(&pen.tilt[1])),
+        buttons: pen.buttons,
+        tool: pen.tool,
+        phase,
+    };
+    let tool = pen.tool;
+
+    if window.pen_proximity.map(|(id, _)| id) != Some(e.sourceid) {
+        // Only one pen can be near the tablet at a time, so a different one showing up means the last one left
+        leave_pen_proximity(window);
+        window.pen_proximity = Some((e.sourceid, tool));
+        window.event_buffer.push(Event::PenProximity { tool, entered: true });
+    }
+    window.event_buffer.push(event);
+}
+
+#[cfg(feature = "input")]
+fn leave_pen_proximity(window: &mut WindowDetails) {
+    if let Some((_, tool)) = window.pen_proximity.take() {
+        window.event_buffer.push(Event::PenProximity { tool, entered: false });
+    }
+}
+
+// Touches which have started but not ended yet, with their last known position.
+// This only deals with the parts of the events it needs, so that it can be tested with recorded ones.
+#[cfg(feature = "input")]
+#[derive(Default)]
+pub(crate) struct Touches(Vec<(u32, (f64, f64))>);
+
+#[cfg(feature = "input")]
+impl Touches {
+    // Takes the type, touch ID (the detail field) and position of an XI2 touch event
+    pub(crate) fn event(&mut self, event_type: u16, id: u32, position: (f64, f64)) -> Option<Event> {
+        let touch = self.0.iter().position(|(touch, _)| *touch == id);
+        let phase = match (event_type, touch) {
+            (XCB_INPUT_TOUCH_BEGIN, _) => {
+                self.0.push((id, position));
+                TouchPhase::Started
+            },
+            (XCB_INPUT_TOUCH_UPDATE, Some(i)) => {
+                self.0[i].1 = position;
+                TouchPhase::Moved
+            },
+            (XCB_INPUT_TOUCH_END, Some(i)) => {
+                let _ = self.0.swap_remove(i);
+                TouchPhase::Ended
+            },
+            // A touch we never saw start, probably from before the window was mapped
+            _ => return None,
+        };
+        Some(Event::Touch { id: id.into(), phase, position })
+    }
+
+    // Ends every touch there is, for when we won't be hearing about them any more
+    pub(crate) fn cancel(&mut self, events: &mut Vec<Event>) {
+        for (id, position) in self.0.drain(..) {
+            events.push(Event::Touch { id: id.into(), phase: TouchPhase::Cancelled, position });
+        }
+    }
+}
+
+#[cfg(feature = "input")]
+#[repr(C)]
+struct XiMask {
+    head: xcb_input_event_mask_t,
+    body: [u32; 2],
+}
+
 // assumes we hold connection lock
 unsafe fn internal_set_maximised(c: *mut xcb_connection_t, xid: xcb_window_t, details: &ConnectionDetails, maximised: bool) {
     let action = if maximised { 1 } else { 0 };
@@ -1004,13 +2267,71 @@ unsafe fn internal_set_title(c: *mut xcb_connection_t, xid: xcb_window_t, atoms:
     );
 }
 
+// Finds a 32-bit TrueColor visual, whose 8 bits not covered by the colour masks are used by compositors as alpha
+unsafe fn argb_visual(screen: *const xcb_screen_t) -> Option<xcb_visualid_t> {
+    let mut depths = xcb_screen_allowed_depths_iterator(screen);
+    while depths.rem > 0 {
+        if (*depths.data).depth == 32 {
+            let visuals = xcb_depth_visuals_iterator(depths.data);
+            let visuals = std::slice::from_raw_parts(visuals.data, visuals.rem as usize);
+            let visual = visuals.iter().find(|v| {
+                v.class == XCB_VISUAL_CLASS_TRUE_COLOR && (v.red_mask | v.green_mask | v.blue_mask) != !0
+            });
+            if let Some(visual) = visual {
+                return Some(visual.visual_id)
+            }
+        }
+        xcb_depth_next(&mut depths);
+    }
+    None
+}
+
+// Clamps an opacity we've been given into range, where NaN would otherwise slip through and end up written as 0
+fn clamp_opacity(opacity: f32) -> f32 {
+    if opacity.is_nan() { 1.0 } else { opacity.clamp(0.0, 1.0) }
+}
+
+// A missing property means fully opaque, and compositors can skip blending a window which doesn't have one
+unsafe fn internal_set_opacity(c: *mut xcb_connection_t, xid: xcb_window_t, atoms: &Atoms, opacity: f32) {
+    if opacity >= 1.0 {
+        let _ = xcb_delete_property(c, xid, atoms._net_wm_window_opacity);
+    } else {
+        let value = (f64::from(opacity) * f64::from(u32::MAX)) as u32;
+        let _ = xcb_change_property(
+            c,
+            XCB_PROP_MODE_REPLACE,
+            xid,
+            atoms._net_wm_window_opacity,
+            XCB_ATOM_CARDINAL,
+            32,
+            1,
+            (&value) as *const u32 as _,
+        );
+    }
+}
+
 #[cfg(feature = "input")]
 use crate::input::Key;
+
+#[cfg(feature = "input")]
+fn mouse_button(button: u32) -> Option<MouseButton> {
+    // Buttons 4 to 7 are the scroll wheel, which aren't really buttons
+    match button {
+        1 => Some(MouseButton::Left),
+        2 => Some(MouseButton::Middle),
+        3 => Some(MouseButton::Right),
+        8 => Some(MouseButton::Back),
+        9 => Some(MouseButton::Forward),
+        10..=255 => Some(MouseButton::Other(button as u8)),
+        _ => None,
+    }
+}
+
 #[cfg(feature = "input")]
-fn keysym_to_key(keysym: KeySym, keysym2: KeySym) -> Option<Key> {
-    // This function converts a keysym, as returned by XLookupKeysym, to a ramen key.
+pub(super) fn keysym_to_key(keysym: xkb_keysym_t, keysym2: xkb_keysym_t) -> Option<Key> {
+    // This function converts a keysym, as returned by `Keyboard::key_get_syms`, to a ramen key.
     // X does have multiple keysyms per key (for example, XK_A vs XK_a depending if shift is held),
-    // however, XLookupKeysym ignores all modifiers, so this function should only receive "base" keysym values.
+    // however, the first keysym ignores all modifiers, so this function should only receive "base" keysym values.
     // To avoid some annoying situations we also request keysym2 which is the modified keysym.
     // Values mostly copied from <X11/keysymdef.h>
     match keysym {
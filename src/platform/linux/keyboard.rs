@@ -0,0 +1,403 @@
+// Keyboard handling through libxkbcommon
+//
+// The keymap and state come from the server through xkbcommon-x11, and are kept up to date by XKB's
+// NewKeyboardNotify, MapNotify and StateNotify events, so that keys are translated the same way the rest of
+// the desktop translates them, with all the layout groups and shift levels XKB knows about.
+//
+// Dead keys and the Compose key don't produce any text by themselves, instead they start a sequence of keysyms which
+// is looked up in the user's compose table (see Compose(5)). We keep one compose state per window, so that a sequence
+// started in one window can't be finished in another.
+
+use super::{ffi::*, imp::{keysym_to_key, modifiers_from_mask}};
+use crate::{input::{Key, KeyboardLayouts, Modifiers, ScanCode}};
+
+use std::{env, ffi::{CStr, CString}, ptr};
+
+pub(super) struct Keyboard {
+    connection: *mut xcb_connection_t,
+    context: *mut xkb_context,
+    device_id: i32,
+    // The response type of every XKB event
+    event_base: u8,
+    keymap: *mut xkb_keymap,
+    state: *mut xkb_state,
+    // The active layout, as of the last StateNotify
+    layout: xkb_layout_index_t,
+    // Incremented whenever the active layout or the set of layouts changes, so that windows can tell when to report it
+    layout_serial: u32,
+    // Null if there's no compose table for the user's locale
+    compose_table: *mut xkb_compose_table,
+    // Whether `xkb_keymap_key_get_mods_for_level` is available, which needs libxkbcommon 1.0
+    has_mods_for_level: bool,
+}
+
+pub(super) struct Compose {
+    state: *mut xkb_compose_state,
+}
+
+// What should happen with a key press after it's been fed into a compose state
+pub(super) enum ComposeResult {
+    // The key isn't part of any sequence, so it should produce text as normal
+    Unhandled,
+    // The key is part of a sequence, either one that's still in progress or one that was cancelled
+    Consumed,
+    // The key finished a sequence, which produced this text and keysym. Either one may be missing (empty or 0),
+    // as compose sequences don't have to give both.
+    Composed(String, xkb_keysym_t),
+}
+
+unsafe impl Send for Keyboard {}
+unsafe impl Send for Compose {}
+unsafe impl Sync for Compose {}
+
+impl Keyboard {
+    // Returns `None` if the libraries aren't installed, or the server or xkbcommon can't give us a keymap, since keys
+    // can still be reported without one
+    pub(super) unsafe fn new(connection: *mut xcb_connection_t) -> Option<Self> {
+        libxcb_xkb::load().ok()?;
+        libxkbcommon::load().ok()?;
+        libxkbcommon_x11::load().ok()?;
+
+        let mut event_base = 0;
+        if xkb_x11_setup_xkb_extension(
+            connection,
+            XKB_X11_MIN_MAJOR_XKB_VERSION,
+            XKB_X11_MIN_MINOR_XKB_VERSION,
+            XKB_X11_SETUP_XKB_EXTENSION_NO_FLAGS,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut event_base,
+            ptr::null_mut(),
+        ) == 0 {
+            return None
+        }
+        let device_id = xkb_x11_get_core_keyboard_device_id(connection);
+        if device_id == -1 {
+            return None
+        }
+
+        let context = xkb_context_new(XKB_CONTEXT_NO_FLAGS);
+        if context.is_null() {
+            return None
+        }
+        let (keymap, state) = match load_keymap(context, connection, device_id) {
+            Some(x) => x,
+            None => {
+                xkb_context_unref(context);
+                return None
+            },
+        };
+
+        // Every XKB event has a bunch of optional details, but selecting all of them is simplest
+        let events = XCB_XKB_EVENT_TYPE_NEW_KEYBOARD_NOTIFY | XCB_XKB_EVENT_TYPE_MAP_NOTIFY | XCB_XKB_EVENT_TYPE_STATE_NOTIFY;
+        let _ = xcb_xkb_select_events(connection, XCB_XKB_ID_USE_CORE_KBD, events, 0, events, 0, 0, ptr::null());
+
+        // This is the same lookup order as setlocale(3) uses, without relying on the program having called it
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(env::var_os)
+            .find(|value| !value.is_empty())
+            .and_then(|value| CString::new(value.into_string().ok()?).ok())
+            .unwrap_or_else(|| CString::new("C").unwrap());
+        let compose_table = xkb_compose_table_new_from_locale(context, locale.as_ptr(), XKB_COMPOSE_COMPILE_NO_FLAGS);
+
+        Some(Self {
+            connection,
+            context,
+            device_id,
+            event_base,
+            keymap,
+            state,
+            layout: xkb_state_serialize_layout(state, XKB_STATE_LAYOUT_EFFECTIVE),
+            layout_serial: 0,
+            compose_table,
+            has_mods_for_level: libxkbcommon_mods::load().is_ok(),
+        })
+    }
+
+    // Updates the keymap or state if this is an XKB event. Any event can be passed in, others are ignored.
+    pub(super) unsafe fn process_event(&mut self, ev: *mut xcb_generic_event_t) {
+        if (*ev).response_type & !(1 << 7) != self.event_base {
+            return
+        }
+        let event = &*(ev as *mut xcb_xkb_generic_event_t);
+        match event.xkb_type {
+            XCB_XKB_NEW_KEYBOARD_NOTIFY | XCB_XKB_MAP_NOTIFY => {
+                // A new keyboard might be a different device entirely, so look it up again
+                let device_id = xkb_x11_get_core_keyboard_device_id(self.connection);
+                if device_id == -1 || (event.xkb_type == XCB_XKB_MAP_NOTIFY && i32::from(event.device_id) != device_id) {
+                    return
+                }
+                // If this fails we carry on with the old keymap, which is better than nothing
+                if let Some((keymap, state)) = load_keymap(self.context, self.connection, device_id) {
+                    xkb_state_unref(self.state);
+                    xkb_keymap_unref(self.keymap);
+                    self.device_id = device_id;
+                    self.keymap = keymap;
+                    self.state = state;
+                    self.layout = xkb_state_serialize_layout(state, XKB_STATE_LAYOUT_EFFECTIVE);
+                    self.layout_serial = self.layout_serial.wrapping_add(1);
+                }
+            },
+            XCB_XKB_STATE_NOTIFY if i32::from(event.device_id) == self.device_id => {
+                let event = &*(ev as *mut xcb_xkb_state_notify_event_t);
+                let _ = xkb_state_update_mask(
+                    self.state,
+                    event.base_mods.into(),
+                    event.latched_mods.into(),
+                    event.locked_mods.into(),
+                    event.base_group as xkb_layout_index_t,
+                    event.latched_group as xkb_layout_index_t,
+                    event.locked_group.into(),
+                );
+                if xkb_layout_index_t::from(event.group) != self.layout {
+                    self.layout = event.group.into();
+                    self.layout_serial = self.layout_serial.wrapping_add(1);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    pub(super) fn layout_serial(&self) -> u32 {
+        self.layout_serial
+    }
+
+    pub(super) unsafe fn layouts(&self) -> KeyboardLayouts {
+        let names = (0..xkb_keymap_num_layouts(self.keymap))
+            .map(|idx| {
+                let name = xkb_keymap_layout_get_name(self.keymap, idx);
+                if name.is_null() {
+                    String::new()
+                } else {
+                    CStr::from_ptr(name).to_string_lossy().into_owned()
+                }
+            })
+            .collect();
+        KeyboardLayouts { names, active: self.layout as usize }
+    }
+
+    // Locks the given layout group. The change is reported back to us with a StateNotify like any other.
+    pub(super) unsafe fn set_layout(&self, idx: usize) {
+        if let Ok(idx) = u8::try_from(idx) {
+            if xkb_layout_index_t::from(idx) < xkb_keymap_num_layouts(self.keymap) {
+                let _ = xcb_xkb_latch_lock_state(self.connection, XCB_XKB_ID_USE_CORE_KBD, 0, 0, 1, idx, 0, 0, 0);
+                let _ = xcb_flush(self.connection);
+            }
+        }
+    }
+
+    // Returns what the key produces in the active layout, for showing to the user
+    pub(super) unsafe fn key_label(&self, key: Key) -> String {
+        let keysym = match key {
+            Key::Unknown(keysym) => Some(keysym),
+            _ => self.keycodes()
+                .map(|keycode| self.level_sym(keycode, 0))
+                .find(|&keysym| keysym != 0 && keysym_to_key(keysym, keysym) == Some(key))
+                .or_else(|| {
+                    // The layout doesn't have this key, so label whichever key is in its place on a US keyboard
+                    let keycode = self.keycodes()
+                        .find(|&keycode| ScanCode::from_evdev(keycode.saturating_sub(8)).qwerty_key() == Some(key))?;
+                    Some(self.level_sym(keycode, 0)).filter(|&keysym| keysym != 0)
+                }),
+        };
+
+        if let Some(keysym) = keysym {
+            let ch = char::from_u32(xkb_keysym_to_utf32(keysym))
+                .filter(|ch| *ch != '\0' && !ch.is_control() && !ch.is_whitespace());
+            if let Some(ch) = ch {
+                // Keycaps are usually printed in upper case, but not if that would turn one letter into two (like ß)
+                let mut upper = ch.to_uppercase();
+                return match (upper.next(), upper.next()) {
+                    (Some(upper), None) => upper.to_string(),
+                    _ => ch.to_string(),
+                }
+            }
+            if let Key::Unknown(_) = key {
+                let mut buffer = [0u8; 64];
+                let len = xkb_keysym_get_name(keysym, buffer.as_mut_ptr().cast(), buffer.len());
+                if len > 0 {
+                    return String::from_utf8_lossy(&buffer[..(len as usize).min(buffer.len() - 1)]).into_owned()
+                }
+            }
+        }
+        key.to_string()
+    }
+
+    // Finds a key in the active layout which types the given character, and the modifiers that need to be held for it
+    pub(super) unsafe fn key_for_char(&self, ch: char) -> Option<(Key, Modifiers)> {
+        // Any modifiers outside of these, such as AltGr, can't be expressed with `Modifiers`
+        const EXPRESSIBLE: u32 = XCB_MOD_MASK_SHIFT
+            | XCB_MOD_MASK_LOCK
+            | XCB_MOD_MASK_CONTROL
+            | XCB_MOD_MASK_1
+            | XCB_MOD_MASK_2
+            | XCB_MOD_MASK_4;
+
+        let mut best: Option<(Key, u32)> = None;
+        for keycode in self.keycodes() {
+            let base = self.level_sym(keycode, 0);
+            for level in 0..xkb_keymap_num_levels_for_key(self.keymap, keycode, self.layout) {
+                let keysym = self.level_sym(keycode, level);
+                if keysym == 0 || xkb_keysym_to_utf32(keysym) != u32::from(ch) {
+                    continue
+                }
+                let mut masks = [0; 16];
+                let count = if self.has_mods_for_level {
+                    xkb_keymap_key_get_mods_for_level(
+                        self.keymap,
+                        keycode,
+                        self.layout,
+                        level,
+                        masks.as_mut_ptr(),
+                        masks.len(),
+                    )
+                } else if level == 1 {
+                    // Without being able to ask, assume the usual arrangement where the second level is Shift
+                    masks[0] = XCB_MOD_MASK_SHIFT;
+                    1
+                } else {
+                    0
+                };
+                // Prefer not to rely on Caps Lock, then prefer whatever needs the fewest modifiers
+                let score = |mask: &u32| (mask & XCB_MOD_MASK_LOCK != 0, mask.count_ones());
+                let mask = masks[..count.min(masks.len())]
+                    .iter()
+                    .chain(if level == 0 { Some(&0) } else { None })
+                    .copied()
+                    .filter(|mask| mask & !EXPRESSIBLE == 0)
+                    .min_by_key(score);
+                if let Some(mask) = mask {
+                    if best.map_or(true, |(_, best)| score(&mask) < score(&best)) {
+                        best = Some((keysym_to_key(base, keysym).unwrap_or(Key::Unknown(base)), mask));
+                    }
+                }
+            }
+        }
+        best.map(|(key, mask)| (key, modifiers_from_mask(mask)))
+    }
+
+    fn keycodes(&self) -> impl Iterator<Item = xkb_keycode_t> {
+        unsafe { xkb_keymap_min_keycode(self.keymap)..=xkb_keymap_max_keycode(self.keymap) }
+    }
+
+    // The first keysym produced by a shift level of a key in the active layout, or 0 if there isn't one
+    unsafe fn level_sym(&self, keycode: xkb_keycode_t, level: xkb_level_index_t) -> xkb_keysym_t {
+        let mut syms = ptr::null();
+        if xkb_keymap_key_get_syms_by_level(self.keymap, keycode, self.layout, level, &mut syms) > 0 {
+            *syms
+        } else {
+            0
+        }
+    }
+
+    // Returns the keysym of a key with no modifiers held, and the keysym it actually produces.
+    // Events can sit in a window's queue for a while before being processed, so rather than trusting that our state
+    // is still current, it's brought in line with the modifiers and group the server sent along with the key event.
+    pub(super) unsafe fn key_get_syms(
+        &mut self,
+        keycode: xkb_keycode_t,
+        mods: &xcb_input_modifier_info_t,
+        group: &xcb_input_group_info_t,
+    ) -> (xkb_keysym_t, xkb_keysym_t) {
+        let _ = xkb_state_update_mask(
+            self.state,
+            mods.base,
+            mods.latched,
+            mods.locked,
+            group.base.into(),
+            group.latched.into(),
+            group.locked.into(),
+        );
+        let layout = xkb_state_key_get_layout(self.state, keycode);
+        let mut syms = ptr::null();
+        let unmodified = if xkb_keymap_key_get_syms_by_level(self.keymap, keycode, layout, 0, &mut syms) > 0 {
+            *syms
+        } else {
+            0
+        };
+        (unmodified, xkb_state_key_get_one_sym(self.state, keycode))
+    }
+
+    pub(super) unsafe fn create_compose(&self) -> Option<Compose> {
+        if self.compose_table.is_null() {
+            return None
+        }
+        let state = xkb_compose_state_new(self.compose_table, XKB_COMPOSE_STATE_NO_FLAGS);
+        if state.is_null() {
+            None
+        } else {
+            Some(Compose { state })
+        }
+    }
+}
+
+impl Drop for Keyboard {
+    fn drop(&mut self) {
+        // Compose states hold their own reference to the table, so they can safely outlive this
+        unsafe {
+            if !self.compose_table.is_null() {
+                xkb_compose_table_unref(self.compose_table);
+            }
+            xkb_state_unref(self.state);
+            xkb_keymap_unref(self.keymap);
+            xkb_context_unref(self.context);
+        }
+    }
+}
+
+impl Compose {
+    pub(super) unsafe fn feed(&mut self, keysym: u32) -> ComposeResult {
+        if xkb_compose_state_feed(self.state, keysym) != XKB_COMPOSE_FEED_ACCEPTED {
+            // Ignored keysyms, such as modifiers, don't interrupt a sequence
+            return ComposeResult::Unhandled
+        }
+        match xkb_compose_state_get_status(self.state) {
+            XKB_COMPOSE_COMPOSING => ComposeResult::Consumed,
+            XKB_COMPOSE_CANCELLED => {
+                self.reset();
+                ComposeResult::Consumed
+            },
+            XKB_COMPOSE_COMPOSED => {
+                // Calling with a null buffer gets us the length, not including the null terminator
+                let len = xkb_compose_state_get_utf8(self.state, ptr::null_mut(), 0);
+                let mut buffer = vec![0u8; len.max(0) as usize + 1];
+                let _ = xkb_compose_state_get_utf8(self.state, buffer.as_mut_ptr().cast(), buffer.len());
+                let keysym = xkb_compose_state_get_one_sym(self.state);
+                self.reset();
+                buffer.truncate(len.max(0) as usize);
+                ComposeResult::Composed(String::from_utf8_lossy(&buffer).into_owned(), keysym)
+            },
+            _ => ComposeResult::Unhandled,
+        }
+    }
+
+    pub(super) unsafe fn reset(&mut self) {
+        xkb_compose_state_reset(self.state);
+    }
+}
+
+impl Drop for Compose {
+    fn drop(&mut self) {
+        unsafe {
+            xkb_compose_state_unref(self.state);
+        }
+    }
+}
+
+unsafe fn load_keymap(
+    context: *mut xkb_context,
+    connection: *mut xcb_connection_t,
+    device_id: i32,
+) -> Option<(*mut xkb_keymap, *mut xkb_state)> {
+    let keymap = xkb_x11_keymap_new_from_device(context, connection, device_id, XKB_KEYMAP_COMPILE_NO_FLAGS);
+    if keymap.is_null() {
+        return None
+    }
+    let state = xkb_x11_state_new_from_device(keymap, connection, device_id);
+    if state.is_null() {
+        xkb_keymap_unref(keymap);
+        return None
+    }
+    Some((keymap, state))
+}
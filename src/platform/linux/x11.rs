@@ -0,0 +1,67 @@
+// Helpers for talking to the X server which are shared by the clipboard, drag and drop and the windows themselves
+
+use super::ffi::*;
+use crate::error::Error;
+
+use std::{ptr, slice};
+
+// Reads the whole of a property, optionally deleting it, returning its type and data
+pub(super) unsafe fn read_property(
+    c: *mut xcb_connection_t,
+    window: xcb_window_t,
+    property: xcb_atom_t,
+    delete: bool,
+) -> Option<(xcb_atom_t, Vec<u8>)> {
+    let cookie = xcb_get_property(c, delete.into(), window, property, XCB_GET_PROPERTY_TYPE_ANY, 0, !0 / 4);
+    let reply = xcb_get_property_reply(c, cookie, ptr::null_mut());
+    if reply.is_null() {
+        return None
+    }
+    let r#type = (*reply).r#type;
+    let len = xcb_get_property_value_length(reply) as usize;
+    let data = slice::from_raw_parts(xcb_get_property_value(reply).cast::<u8>(), len).to_vec();
+    free(reply.cast());
+    Some((r#type, data))
+}
+
+// Gets the names of all the given atoms with one round trip, skipping any which don't exist
+pub(super) unsafe fn atom_names(c: *mut xcb_connection_t, atoms: &[xcb_atom_t]) -> Vec<String> {
+    let cookies = atoms.iter().map(|&atom| xcb_get_atom_name(c, atom)).collect::<Vec<_>>();
+    let mut names = Vec::with_capacity(cookies.len());
+    for cookie in cookies {
+        let mut err: *mut xcb_generic_error_t = ptr::null_mut();
+        let reply = xcb_get_atom_name_reply(c, cookie, &mut err);
+        if reply.is_null() {
+            free(err.cast());
+            continue
+        }
+        let name = xcb_get_atom_name_name(reply).cast::<u8>();
+        let len = xcb_get_atom_name_name_length(reply) as usize;
+        names.push(String::from_utf8_lossy(slice::from_raw_parts(name, len)).into_owned());
+        free(reply.cast());
+    }
+    names
+}
+
+// Interns all the given atoms with one round trip
+pub(super) unsafe fn intern_atoms(
+    c: *mut xcb_connection_t,
+    names: &[&str],
+    atoms: &mut [xcb_atom_t],
+) -> Result<(), Error> {
+    let cookies = names
+        .iter()
+        .map(|name| xcb_intern_atom(c, 0, name.len() as u16, name.as_ptr().cast()))
+        .collect::<Vec<_>>();
+    let mut result = Ok(());
+    for (atom, cookie) in atoms.iter_mut().zip(cookies) {
+        let reply = xcb_intern_atom_reply(c, cookie, ptr::null_mut());
+        if reply.is_null() {
+            result = Err(Error::SystemResources);
+        } else {
+            *atom = (*reply).atom;
+            free(reply.cast());
+        }
+    }
+    result
+}
@@ -376,9 +376,11 @@ pub(crate) const WM_MBUTTONUP: UINT = 0x0208;
 pub(crate) const WM_MOUSEWHEEL: UINT = 0x020A;
 pub(crate) const WM_XBUTTONDOWN: UINT = 0x020B;
 pub(crate) const WM_XBUTTONUP: UINT = 0x020C;
+pub(crate) const WM_MOUSEHWHEEL: UINT = 0x020E;
 pub(crate) const WM_MOVING: UINT = 0x0216;
 pub(crate) const WM_EXITSIZEMOVE: UINT = 0x0232;
 pub(crate) const WM_USER: UINT = 0x0400;
+pub(crate) const WHEEL_DELTA: i16 = 120;
 pub(crate) const WS_BORDER: DWORD = 0x00800000;
 pub(crate) const WS_CAPTION: DWORD = 0x00C00000;
 pub(crate) const WS_CHILD: DWORD = 0x40000000;
@@ -586,6 +588,7 @@ extern "system" {
     ) -> HWND;
     pub(crate) fn AdjustWindowRectEx(lpRect: *mut RECT, dwStyle: DWORD, bMenu: BOOL, dwExStyle: DWORD) -> BOOL;
     pub(crate) fn ClientToScreen(hWnd: HWND, lpPoint: *mut POINT) -> BOOL;
+    pub(crate) fn ScreenToClient(hWnd: HWND, lpPoint: *mut POINT) -> BOOL;
     pub(crate) fn GetClientRect(hWnd: HWND, lpRect: *mut RECT) -> BOOL;
     pub(crate) fn GetWindowRect(hWnd: HWND, lpRect: *mut RECT) -> BOOL;
     pub(crate) fn GetTitleBarInfo(hwnd: HWND, pti: *mut TITLEBARINFO) -> BOOL;
@@ -637,6 +640,7 @@ extern "system" {
     pub(crate) fn GetCapture() -> HWND;
     pub(crate) fn ReleaseCapture() -> BOOL;
     pub(crate) fn GetAsyncKeyState(vKey: c_int) -> SHORT;
+    pub(crate) fn GetKeyState(nVirtKey: c_int) -> SHORT;
     pub(crate) fn GetSystemMetrics(nIndex: c_int) -> c_int;
     pub(crate) fn SetCursor(hCursor: HCURSOR) -> HCURSOR;
     pub(crate) fn MapVirtualKeyW(hCode: UINT, uMapType: UINT) -> UINT;
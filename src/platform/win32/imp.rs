@@ -13,7 +13,7 @@ use crate::{
 };
 
 #[cfg(feature = "input")]
-use crate::input::{Key, MouseButton};
+use crate::input::{Key, Modifiers, MouseButton, ScanCode, ScrollUnit};
 
 use std::{cell::UnsafeCell, mem, ptr};
 
@@ -395,6 +395,8 @@ struct WindowState {
     dpi: UINT,
     is_max: bool,
     is_min: bool,
+    #[cfg(feature = "input")]
+    modifiers: Modifiers,
     style: Style,
     wh: (u16, u16),
 }
@@ -447,6 +449,8 @@ unsafe fn make_window(builder: window::Builder) -> Result<Window, Error> {
         dpi,
         is_max: false,
         is_min: false,
+        #[cfg(feature = "input")]
+        modifiers: Modifiers::empty(),
         style: builder.style,
         wh: builder.size,
     }));
@@ -540,6 +544,47 @@ impl Window {
         }
     }
 
+    pub(crate) fn cursor_position(&self) -> Option<(i16, i16)> {
+        unsafe {
+            let mut point: POINT = mem::zeroed();
+            if GetCursorPos(&mut point) != 0 && ScreenToClient(self.hwnd, &mut point) != 0 {
+                Some((point.x as i16, point.y as i16))
+            } else {
+                None
+            }
+        }
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn mouse_buttons(&self) -> Vec<MouseButton> {
+        [
+            (VK_LBUTTON, MouseButton::Left),
+            (VK_MBUTTON, MouseButton::Middle),
+            (VK_RBUTTON, MouseButton::Right),
+            (VK_XBUTTON1, MouseButton::Back),
+            (VK_XBUTTON2, MouseButton::Forward),
+        ]
+        .iter()
+        .filter(|(vk, _)| unsafe { GetAsyncKeyState((*vk).into()) } as u16 & 0x8000 != 0)
+        .map(|(_, button)| *button)
+        .collect()
+    }
+
+    #[cfg(feature = "input")]
+    pub(crate) fn modifiers(&self) -> Modifiers {
+        // Key state is per-thread, so this is whatever the window thread last saw
+        unsafe { (*self.state.get()).modifiers }
+    }
+
+    pub(crate) fn set_cursor_position(&self, (x, y): (i16, i16)) {
+        unsafe {
+            let mut point = POINT { x: x.into(), y: y.into() };
+            if ClientToScreen(self.hwnd, &mut point) != 0 {
+                let _ = SetCursorPos(point.x, point.y);
+            }
+        }
+    }
+
     pub(crate) fn set_resizable(&self, resizable: bool) {
         unsafe {
             let state = &mut *user_state(self.hwnd);
@@ -611,6 +656,24 @@ impl WindowState {
         self.event_backbuf.push(event);
         mem::drop(guard);
     }
+
+    /// Reads the modifier state as of the message being processed, dispatching `ModifiersChanged` if it changed.
+    #[cfg(feature = "input")]
+    unsafe fn update_modifiers(&mut self) {
+        let held = |vk: u8| GetKeyState(vk.into()) < 0;
+        let toggled = |vk: u8| GetKeyState(vk.into()) & 1 != 0;
+        let mut modifiers = Modifiers::empty();
+        modifiers.set(Modifiers::SHIFT, held(VK_SHIFT));
+        modifiers.set(Modifiers::CTRL, held(VK_CONTROL));
+        modifiers.set(Modifiers::ALT, held(VK_MENU));
+        modifiers.set(Modifiers::SUPER, held(VK_LWIN) || held(VK_RWIN));
+        modifiers.set(Modifiers::CAPS_LOCK, toggled(VK_CAPITAL));
+        modifiers.set(Modifiers::NUM_LOCK, toggled(VK_NUMLOCK));
+        if self.modifiers != modifiers {
+            self.modifiers = modifiers;
+            self.dispatch_event(Event::ModifiersChanged(modifiers));
+        }
+    }
 }
 
 /// Returns a pointer to the `WindowState` for a ramen window.
@@ -633,20 +696,71 @@ fn extend_key(key: Key, lparam: LPARAM) -> Key {
 }
 
 #[cfg(feature = "input")]
-fn map_tr_state(key: Key, lparam: LPARAM) -> Event {
+fn map_tr_state(key: Key, modifiers: Modifiers, lparam: LPARAM) -> Event {
+    // Bits 16-23 are the scan code, and bit 24 is set if it had the 0xE0 prefix
+    let keycode = ((lparam >> 16) & 0xFF) as u32 | if (lparam & (1 << 24)) != 0 { 0xE000 } else { 0 };
+    let scancode = translate_scancode(keycode);
     if (lparam & (1 << 31)) == 0 {
         if (lparam & (1 << 30)) != 0 {
-            Event::KeyboardRepeat(key)
+            Event::KeyboardRepeat { key, scancode, keycode, modifiers }
         } else {
-            Event::KeyboardDown(key)
+            Event::KeyboardDown { key, scancode, keycode, modifiers }
         }
     } else {
-        Event::KeyboardUp(key)
+        Event::KeyboardUp { key, scancode, keycode, modifiers }
     }
 }
 
+/// Converts a PC/AT "set 1" scan code, as Windows reports them, to evdev numbering.
+#[cfg(feature = "input")]
+fn translate_scancode(keycode: u32) -> ScanCode {
+    let evdev = match keycode {
+        // Windows reports Pause with NumLock's scan code, and gives the real NumLock the extended bit instead.
+        0x0045 => 119,
+        0xE045 => 69,
+
+        // SysRq, which is what you get for Alt+PrintScreen
+        0x0054 => 99,
+
+        // F13 to F24
+        0x0064..=0x006E => keycode - 0x64 + 183,
+
+        // Everything else before the extended keys is identical.
+        0x0001..=0x0058 => keycode,
+
+        0xE010 => 165, // previous track
+        0xE019 => 163, // next track
+        0xE01C => 96,  // keypad enter
+        0xE01D => 97,  // right control
+        0xE020 => 113, // mute
+        0xE022 => 164, // play/pause
+        0xE024 => 166, // stop
+        0xE02E => 114, // volume down
+        0xE030 => 115, // volume up
+        0xE035 => 98,  // keypad divide
+        0xE037 => 99,  // print screen
+        0xE038 => 100, // right alt
+        0xE047 => 102, // home
+        0xE048 => 103, // up
+        0xE049 => 104, // page up
+        0xE04B => 105, // left
+        0xE04D => 106, // right
+        0xE04F => 107, // end
+        0xE050 => 108, // down
+        0xE051 => 109, // page down
+        0xE052 => 110, // insert
+        0xE053 => 111, // delete
+        0xE05B => 125, // left windows
+        0xE05C => 126, // right windows
+        0xE05D => 127, // applications
+        0xE05F => 142, // sleep
+        _ => 0,
+    };
+    ScanCode::from_evdev(evdev)
+}
+
 #[cfg(feature = "input")]
-fn sys_key_event(wparam: WPARAM, lparam: LPARAM) -> Option<Event> {
+fn sys_key_event(wparam: WPARAM, modifiers: Modifiers, lparam: LPARAM) -> Option<Event> {
     let alt_bit = (lparam & (1 << 29)) != 0;
     let transition_state = (lparam & (1 << 31)) != 0;
 
@@ -656,10 +770,10 @@ fn sys_key_event(wparam: WPARAM, lparam: LPARAM) -> Option<Event> {
         return None
     }
 
-    let virtual_key = translate_vk(wparam)?;
+    let virtual_key = translate_vk(wparam).unwrap_or(Key::Unknown((wparam & 0xFF) as u32));
     let key = extend_key(virtual_key, lparam);
 
-    Some(map_tr_state(key, lparam))
+    Some(map_tr_state(key, modifiers, lparam))
 }
 
 #[cfg(feature = "input")]
@@ -1046,7 +1160,11 @@ pub unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM,
         // This is mainly intended for textbox controls but works perfectly fine for actual windows.
         // See also: `WM_ACTIVATE` (to know why this is used for focus events)
         WM_SETFOCUS => {
-            (*user_state(hwnd)).dispatch_event(Event::Focus(true));
+            let state = &mut *user_state(hwnd);
+            // Modifiers may have been pressed or released while we were out of focus
+            #[cfg(feature = "input")]
+            state.update_modifiers();
+            state.dispatch_event(Event::Focus(true));
             0
         },
 
@@ -1096,8 +1214,11 @@ pub unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM,
         // Return 0.
         WM_KEYDOWN | WM_KEYUP => {
             #[cfg(feature = "input")]
-            if let Some(key) = translate_vk(wparam) {
-                (*user_state(hwnd)).dispatch_event(map_tr_state(extend_key(key, lparam), lparam));
+            {
+                let key = translate_vk(wparam).unwrap_or(Key::Unknown((wparam & 0xFF) as u32));
+                let state = &mut *user_state(hwnd);
+                state.dispatch_event(map_tr_state(extend_key(key, lparam), state.modifiers, lparam));
+                state.update_modifiers();
             }
             0
         },
@@ -1123,9 +1244,12 @@ pub unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM,
             // Except if it's the Alt key being released, it won't be set! So you must trust wParam.
             // F10 doesn't even set any bit because there's no F10 bit, so you trust that one too.
             #[cfg(feature = "input")]
-            if let Some(event) = sys_key_event(wparam, lparam) {
+            {
                 let state = &mut *user_state(hwnd);
-                state.dispatch_event(event);
+                if let Some(event) = sys_key_event(wparam, state.modifiers, lparam) {
+                    state.dispatch_event(event);
+                }
+                state.update_modifiers();
             }
 
             0
@@ -1171,6 +1295,26 @@ pub unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM,
                 } else if delta < 0 {
                     state.dispatch_event(Event::ScrollDown);
                 }
+                state.dispatch_event(Event::Scroll {
+                    dx: 0.0,
+                    dy: f64::from(delta) / f64::from(WHEEL_DELTA),
+                    unit: ScrollUnit::Lines,
+                });
+            }
+            0
+        },
+
+        // Same as WM_MOUSEWHEEL but for the horizontal wheel, and positive values mean right rather than up
+        WM_MOUSEHWHEEL => {
+            #[cfg(feature = "input")]
+            {
+                let state = &mut *user_state(hwnd);
+                let delta = (wparam >> 16) as u16 as i16;
+                state.dispatch_event(Event::Scroll {
+                    dx: f64::from(delta) / f64::from(WHEEL_DELTA),
+                    dy: 0.0,
+                    unit: ScrollUnit::Lines,
+                });
             }
             0
         },
@@ -1223,7 +1367,8 @@ pub unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM,
             #[cfg(feature = "input")]
             {
                 let state = &mut *user_state(hwnd);
-                state.dispatch_event(Event::MouseDown(MouseButton::Left));
+                state.update_modifiers();
+                state.dispatch_event(Event::MouseDown { button: MouseButton::Left, modifiers: state.modifiers });
             }
             0
         },
@@ -1231,7 +1376,8 @@ pub unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM,
             #[cfg(feature = "input")]
             {
                 let state = &mut *user_state(hwnd);
-                state.dispatch_event(Event::MouseDown(MouseButton::Right));
+                state.update_modifiers();
+                state.dispatch_event(Event::MouseDown { button: MouseButton::Right, modifiers: state.modifiers });
             }
             0
         },
@@ -1239,7 +1385,8 @@ pub unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM,
             #[cfg(feature = "input")]
             {
                 let state = &mut *user_state(hwnd);
-                state.dispatch_event(Event::MouseDown(MouseButton::Middle));
+                state.update_modifiers();
+                state.dispatch_event(Event::MouseDown { button: MouseButton::Middle, modifiers: state.modifiers });
             }
             0
         },
@@ -1247,7 +1394,8 @@ pub unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM,
             #[cfg(feature = "input")]
             {
                 let state = &mut *user_state(hwnd);
-                state.dispatch_event(Event::MouseUp(MouseButton::Left));
+                state.update_modifiers();
+                state.dispatch_event(Event::MouseUp { button: MouseButton::Left, modifiers: state.modifiers });
             }
             0
         },
@@ -1255,7 +1403,8 @@ pub unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM,
             #[cfg(feature = "input")]
             {
                 let state = &mut *user_state(hwnd);
-                state.dispatch_event(Event::MouseUp(MouseButton::Right));
+                state.update_modifiers();
+                state.dispatch_event(Event::MouseUp { button: MouseButton::Right, modifiers: state.modifiers });
             }
             0
         },
@@ -1263,11 +1412,37 @@ pub unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM,
             #[cfg(feature = "input")]
             {
                 let state = &mut *user_state(hwnd);
-                state.dispatch_event(Event::MouseUp(MouseButton::Middle));
+                state.update_modifiers();
+                state.dispatch_event(Event::MouseUp { button: MouseButton::Middle, modifiers: state.modifiers });
             }
             0
         },
 
+        // Received when one of the "X" (thumb) buttons is pressed or released.
+        // wParam: HIWORD = which X button, LOWORD = state of other buttons and modifiers
+        // Unlike the other button messages, return TRUE.
+        WM_XBUTTONDOWN | WM_XBUTTONUP => {
+            #[cfg(feature = "input")]
+            {
+                let state = &mut *user_state(hwnd);
+                let button = match (wparam >> 16) as WORD {
+                    XBUTTON1 => Some(MouseButton::Back),
+                    XBUTTON2 => Some(MouseButton::Forward),
+                    _ => None,
+                };
+                if let Some(button) = button {
+                    state.update_modifiers();
+                    let modifiers = state.modifiers;
+                    state.dispatch_event(if msg == WM_XBUTTONDOWN {
+                        Event::MouseDown { button, modifiers }
+                    } else {
+                        Event::MouseUp { button, modifiers }
+                    });
+                }
+            }
+            TRUE as LRESULT
+        },
+
         WM_SETCURSOR => {
             if (hwnd == wparam as HWND) && ((lparam & 0xFFFF) as WORD == HTCLIENT as WORD) {
                 _ = SetCursor((*user_state(hwnd)).cursor);
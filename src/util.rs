@@ -40,12 +40,15 @@ macro_rules! load {
                         let _ = dlerror();
                         let mut fp = $name.as_mut_ptr().cast::<*mut c_void>();
                         if handle.is_null() { return; }
+                        // A missing function has to fail every call, not just the first one to see dlerror
+                        let mut found = true;
                         $($(#[$inner])* {
                             *fp = dlsym(handle, cstr!(stringify!($fn_name)));
+                            found &= !(*fp).is_null();
                             fp = fp.offset(1);
                         })*
                         _ = fp;
-                        LOADED = true;
+                        LOADED = found;
                     });
                     let err_start = dlerror();
                     if err_start.is_null() {
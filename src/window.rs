@@ -6,7 +6,9 @@ pub use self::{
     decoration::{Controls, Style},
 };
 
-use crate::{event::Event, platform::imp};
+use crate::{dnd::{DragAction, DragData}, error::Error, event::Event, platform::imp};
+#[cfg(feature = "input")]
+use crate::input::{Modifiers, MouseButton};
 
 /// yeah
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -55,6 +57,31 @@ pub enum Cursor {
     Wait,
 }
 
+/// Represents how the mouse cursor is restricted by a [`Window`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CursorGrab {
+    /// The cursor can move freely in and out of the window.
+    None,
+
+    /// The cursor is confined to the inner drawable area of the window while the window has focus.
+    Confined,
+}
+
+/// Represents how the mouse cursor behaves while it's over a [`Window`].
+#[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+#[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CursorMode {
+    /// The cursor is visible and moves freely, reporting its position with [`MouseMove`](Event::MouseMove).
+    Normal,
+
+    /// The cursor is hidden and held in the centre of the window while the window has focus.
+    /// 
+    /// No [`MouseMove`](Event::MouseMove) events are sent in this mode, since the position never meaningfully
+    /// changes. Use [`RawMouseMotion`](Event::RawMouseMotion) to track how the mouse is being moved instead.
+    Locked,
+}
+
 /// Represents an open window. Dropping it closes the window.
 ///
 /// To instantiate windows, use a [`builder`](crate::connection::Connection::builder).
@@ -83,6 +110,131 @@ impl Window {
         }
     }
 
+    /// Sets whether the mouse cursor is confined to the inner drawable area of the window.
+    /// 
+    /// The grab is only held while the window has focus. It will be released when focus is lost, re-applied when
+    /// focus is regained, and released for good when the window is dropped.
+    pub fn set_cursor_grab(&self, grab: CursorGrab) {
+        #[cfg(target_os = "linux")]
+        {
+            self.0.set_cursor_grab(grab)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = grab;
+        }
+    }
+
+    /// Sets whether the mouse cursor is hidden and locked in place over the window.
+    /// 
+    /// As with [`set_cursor_grab`](Self::set_cursor_grab), this only takes effect while the window has focus.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    pub fn set_cursor_mode(&self, mode: CursorMode) {
+        #[cfg(target_os = "linux")]
+        {
+            self.0.set_cursor_mode(mode)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = mode;
+        }
+    }
+
+    /// Returns the current position of the mouse cursor, in pixels relative to the top-left of the window's inner
+    /// drawable area. The cursor may be outside of the window, in which case the position may be negative.
+    /// 
+    /// Returns `None` if the position can't be determined, for example if the cursor is on a different X screen.
+    pub fn cursor_position(&self) -> Option<(i16, i16)> {
+        self.0.cursor_position()
+    }
+
+    /// Moves the mouse cursor to a position in pixels, relative to the top-left of the window's inner drawable area.
+    pub fn set_cursor_position(&self, position: (i16, i16)) {
+        self.0.set_cursor_position(position)
+    }
+
+    /// Returns which mouse buttons are currently being held down.
+    /// 
+    /// Unlike [`MouseDown`](Event::MouseDown) and [`MouseUp`](Event::MouseUp) events, this reflects the state of the
+    /// mouse right now, so it's useful for getting the initial state of the mouse after creating a window.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    pub fn mouse_buttons(&self) -> Vec<MouseButton> {
+        self.0.mouse_buttons()
+    }
+
+    /// Returns which keyboard [`Modifiers`] are currently held or locked.
+    /// 
+    /// Like [`mouse_buttons`](Self::mouse_buttons), this is useful for getting the initial state after creating a
+    /// window. After that, [`ModifiersChanged`](Event::ModifiersChanged) events will keep you up to date.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    pub fn modifiers(&self) -> Modifiers {
+        self.0.modifiers()
+    }
+
+    /// Sets whether the window accepts text from an input method editor (IME), which is how most CJK text is typed.
+    /// 
+    /// While allowed and focused, keys go through the IME first, and the text being composed is reported with
+    /// [`ImePreedit`](Event::ImePreedit) events until it's finished with an [`ImeCommit`](Event::ImeCommit).
+    /// This is disallowed by default, and should only be enabled while the user is editing text.
+    /// 
+    /// Currently this is only implemented on Linux, where the input method is only connected to the first time this
+    /// is allowed. XIM relies on the process locale, so the application must have called `setlocale(LC_CTYPE, "")`
+    /// (or set a UTF-8 locale some other way) beforehand, otherwise there may be no input method available.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    pub fn set_ime_allowed(&self, allowed: bool) {
+        #[cfg(target_os = "linux")]
+        {
+            self.0.set_ime_allowed(allowed)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = allowed;
+        }
+    }
+
+    /// Tells the IME where the text caret is, relative to the top-left of the window's inner drawable area,
+    /// so that its candidate window can be placed next to it rather than covering it up.
+    /// 
+    /// Currently this is only implemented on Linux.
+    #[cfg_attr(feature = "nightly-rustdoc", doc(cfg(feature = "input")))]
+    #[cfg_attr(not(feature = "nightly-rustdoc"), cfg(feature = "input"))]
+    pub fn set_ime_cursor_area(&self, position: (i16, i16), size: (u16, u16)) {
+        #[cfg(target_os = "linux")]
+        {
+            self.0.set_ime_cursor_area(position, size)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = (position, size);
+        }
+    }
+
+    /// Starts dragging something out of the window, which the user can drop into another application. This should be
+    /// called while a mouse button is held, usually after the mouse has moved a few pixels from where it was pressed,
+    /// and the drag follows the mouse until that button is released.
+    /// 
+    /// `allowed_actions` lists what the application it's dropped into may do with it, with the one you'd prefer first.
+    /// If there's more than one, the application may ask the user which they want. An [`Event::DragEnd`] is sent once
+    /// it's over, saying which of them was done. Pressing Escape cancels the drag, and if the application it's dropped
+    /// into doesn't finish with it within 30 seconds, it's treated as cancelled too.
+    /// 
+    /// Currently this is only implemented on Linux, and other platforms will return [`Error::Unsupported`].
+    pub fn start_drag(&self, data: DragData, allowed_actions: &[DragAction]) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        {
+            self.0.start_drag(data, allowed_actions)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = (data, allowed_actions);
+            Err(Error::Unsupported)
+        }
+    }
+
     /// Pulls any new events into the buffer, discarding any events which were previously in the buffer.
     /// 
     /// Query the buffer by calling `events()`.
@@ -130,6 +282,40 @@ impl Window {
         self.0.set_position(position)
     }
 
+    /// Returns the opacity of the whole window, from `0.0` (invisible) to `1.0` (opaque), as set with
+    /// [`set_opacity`](Self::set_opacity) or [`Builder::opacity`].
+    /// 
+    /// On X11 this also picks up changes made by the compositor, once `poll_events()` has seen them.
+    /// 
+    /// Currently this is only implemented on Linux, and other platforms will always return `1.0`.
+    pub fn opacity(&self) -> f32 {
+        #[cfg(target_os = "linux")]
+        {
+            self.0.opacity()
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            1.0
+        }
+    }
+
+    /// Sets the opacity of the whole window, from `0.0` (invisible) to `1.0` (opaque). Values outside of that range
+    /// are clamped to it, and NaN is treated as `1.0`.
+    /// 
+    /// On X11 this needs a compositor to have any visible effect.
+    /// 
+    /// Currently this is only implemented on Linux, and other platforms will ignore it.
+    pub fn set_opacity(&self, opacity: f32) {
+        #[cfg(target_os = "linux")]
+        {
+            self.0.set_opacity(opacity)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = opacity;
+        }
+    }
+
     /// Sets whether the window is resizable either by dragging the outer area or by maximising it.
     /// 
     /// Note that setting this to `false` does not completely prevent the window from changing size; it only prevents
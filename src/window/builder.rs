@@ -11,14 +11,17 @@ use std::borrow::Cow;
 /// Builders can be cloned if you'd like to re-use one to build multiple windows.
 #[derive(Clone)]
 pub struct Builder {
+    pub(crate) accept_drops: bool,
     pub(crate) cursor: Cursor,
     pub(crate) connection: Connection,
     pub(crate) class_name: Cow<'static, str>,
     pub(crate) maximised: bool,
+    pub(crate) opacity: f32,
     pub(crate) position: Option<(i16, i16)>,
     pub(crate) size: (u16, u16),
     pub(crate) style: Style,
     pub(crate) title: Cow<'static, str>,
+    pub(crate) transparent: bool,
     #[cfg(unix)]
     pub(crate) depth: Option<u8>,
     #[cfg(unix)]
@@ -28,10 +31,12 @@ pub struct Builder {
 impl Builder {
     pub(crate) const fn new(connection: Connection, style: Option<Style>) -> Self {
         Builder {
+            accept_drops: false,
             cursor: Cursor::Arrow,
             connection,
             class_name: Cow::Borrowed("ramen_window"),
             maximised: false,
+            opacity: 1.0,
             position: None,
             size: (800, 600),
             style: match style {
@@ -40,6 +45,7 @@ impl Builder {
                 None => Style::new(),
             },
             title: Cow::Borrowed("a nice window"),
+            transparent: false,
             #[cfg(unix)]
             depth: None,
             #[cfg(unix)]
@@ -52,6 +58,18 @@ impl Builder {
         imp::Window::new(self).map(super::Window)
     }
 
+    /// Sets whether things can be dragged onto the window from other applications, such as files from a file
+    /// manager. See [`Event::DragEnter`](crate::event::Event::DragEnter) and
+    /// [`Event::Drop`](crate::event::Event::Drop).
+    /// 
+    /// Currently this is only implemented on Linux.
+    /// 
+    /// Defaults to `false`.
+    pub fn accept_drops(mut self, accept_drops: bool) -> Self {
+        self.accept_drops = accept_drops;
+        self
+    }
+
     /// Sets whether the window should be borderless.
     /// 
     /// Defaults to `false`.
@@ -110,6 +128,22 @@ impl Builder {
         self
     }
 
+    /// Sets whether the window's contents have an alpha channel, so that anything drawn into it with less than full
+    /// alpha lets whatever is behind the window show through. Unlike [`opacity`](Self::opacity), this applies per
+    /// pixel rather than to the whole window.
+    /// 
+    /// On X11 this picks a 32-bit ARGB visual for the window, and needs a compositor to have any visible effect.
+    /// If the screen has no such visual, [`build`](Self::build) returns [`Error::Unsupported`]. It's ignored if a
+    /// visual has been chosen with [`visual`](Self::visual).
+    /// 
+    /// Currently this is only implemented on Linux.
+    /// 
+    /// Defaults to `false`.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
     /// Sets whether the window should begin maximised on the user's monitor.
     /// 
     /// If the user has multiple monitors, the primary monitor will usually be selected.
@@ -122,6 +156,19 @@ impl Builder {
         self
     }
 
+    /// Sets the initial opacity of the whole window, from `0.0` (invisible) to `1.0` (opaque). Values outside of
+    /// that range are clamped to it, and NaN is treated as `1.0`.
+    /// 
+    /// On X11 this needs a compositor to have any visible effect.
+    /// 
+    /// Currently this is only implemented on Linux.
+    /// 
+    /// Defaults to `1.0`.
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
     /// Sets the screen position of the top-left of the window's inner drawable area, in pixels, relative to the
     /// top-left of the user's desktop. If `None`, the operating system will decide where to place the window.
     /// 